@@ -0,0 +1,18 @@
+#version 410 core
+
+in vec2 passUV;
+
+out vec4 Color;
+
+uniform sampler2D difference;   // left minus right height of every grid point
+uniform float max_difference;   // drawn with the most saturated colour
+
+const vec3 LOWER_COLOR = vec3(0.1, 0.3, 0.9);      // left is lower than right
+const vec3 EQUAL_COLOR = vec3(0.97, 0.97, 0.97);
+const vec3 HIGHER_COLOR = vec3(0.85, 0.1, 0.1);
+
+void main() {
+    float t = clamp(texture(difference, passUV).x / max(max_difference, 1e-6), -1., 1.);
+    vec3 color = t < 0. ? mix(EQUAL_COLOR, LOWER_COLOR, -t) : mix(EQUAL_COLOR, HIGHER_COLOR, t);
+    Color = vec4(color, 1.);
+}
\ No newline at end of file
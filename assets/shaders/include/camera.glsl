@@ -0,0 +1,4 @@
+layout (std140) uniform Camera {
+    mat4 mvp_transform;
+    vec3 eye_position;
+};
\ No newline at end of file
@@ -0,0 +1,18 @@
+const int MAX_COLOR_STOPS = 16;
+layout (std140) uniform ColorMap {
+    float color_map_heights[MAX_COLOR_STOPS];
+    vec3 color_map_colors[MAX_COLOR_STOPS];
+    int color_map_size;
+};
+
+vec3 colorMap(in float height) {
+    vec3 color = color_map_colors[0];
+    for (int i = 1; i < color_map_size; ++i) {
+        float low = color_map_heights[i - 1];
+        float high = color_map_heights[i];
+        if (height > low) {
+            color = mix(color_map_colors[i - 1], color_map_colors[i], clamp((height - low) / max(high - low, 1e-6), 0., 1.));
+        }
+    }
+    return color;
+}
\ No newline at end of file
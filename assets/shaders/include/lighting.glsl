@@ -0,0 +1,22 @@
+#include "camera.glsl"
+
+layout (std140) uniform Light {
+    vec3 sun_direction;
+    float sun_ambient;
+    bool sun_specular;
+};
+
+// Sun lit colour of a surface point, specular highlight only when sun_specular is on
+vec3 sunLight(in vec3 albedo, in vec3 normal, in vec3 position, in vec3 specular_color, in float shininess) {
+    vec3 light = normalize(sun_direction);
+
+    float diffuse = max(dot(normal, light), 0.);
+    vec3 color = albedo * (sun_ambient + (1. - sun_ambient) * diffuse);
+
+    if (sun_specular) {
+        vec3 view = normalize(eye_position - position);
+        vec3 reflected = reflect(-light, normal);
+        color += specular_color * pow(max(dot(view, reflected), 0.), shininess);
+    }
+    return color;
+}
\ No newline at end of file
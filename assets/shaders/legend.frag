@@ -0,0 +1,27 @@
+#version 410 core
+
+in vec2 passBar;
+
+out vec4 Color;
+
+#include "include/color_map.glsl"
+uniform float tick_interval;
+
+const vec3 TICK_COLOR = vec3(0.05, 0.05, 0.05);
+const float BORDER = 0.08;      // fraction of bar width
+const float TICK_LENGTH = 0.35;
+
+void main() {
+    float u = passBar.x;
+    float height = passBar.y;
+
+    float level = height / tick_interval;
+    float dist = abs(fract(level - 0.5) - 0.5);
+    float tick = (1. - smoothstep(0., fwidth(level), dist)) * step(1. - TICK_LENGTH, u);
+
+    float border_v = BORDER * fwidth(height) / fwidth(u);
+    bool border = u < BORDER || u > 1. - BORDER || height < border_v || height > 1. - border_v;
+
+    vec3 color = border ? TICK_COLOR : mix(colorMap(height), TICK_COLOR, tick);
+    Color = vec4(color, 1.);
+}
\ No newline at end of file
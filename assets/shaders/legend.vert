@@ -0,0 +1,11 @@
+#version 410 core
+
+layout (location = 0) in vec4 Position;    // (x, y) in NDC, u across the bar, height along it
+
+out vec2 passBar;
+
+void main()
+{
+    gl_Position = vec4(Position.xy, 0., 1.);
+    passBar = Position.zw;
+}
\ No newline at end of file
@@ -0,0 +1,43 @@
+#version 410 core
+
+in vec2 passUV;
+
+out vec4 Color;
+
+uniform sampler2D cells;        // (ground height, water depth) of every column, both in parts of the grid height
+uniform ivec2 cursor;           // (x, z) column, negative when the cursor is off the terrain
+uniform int grid_step;          // columns between coordinate lines
+uniform float depth_full;       // depth drawn with the darkest blue
+
+#include "include/color_map.glsl"
+
+const vec3 SHALLOW_COLOR = vec3(0.55, 0.85, 1.);
+const vec3 DEEP_COLOR = vec3(0.02, 0.1, 0.45);
+const vec3 GRID_COLOR = vec3(0.05, 0.05, 0.05);
+const float GRID_OPACITY = 0.35;
+const vec3 CURSOR_COLOR = vec3(1., 0.2, 0.2);
+
+// 1 on lines every `step` columns, antialiased to one pixel width
+float gridLine(in vec2 column, in float step) {
+    vec2 dist = abs(fract(column / step + 0.5) - 0.5) * step;
+    vec2 line = 1. - smoothstep(vec2(0.), fwidth(column), dist);
+    return max(line.x, line.y);
+}
+
+void main() {
+    ivec2 size = textureSize(cells, 0);
+    vec2 column = passUV * vec2(size);
+    ivec2 texel = min(ivec2(column), size - 1);
+    vec2 cell = texelFetch(cells, texel, 0).xy;
+
+    vec3 color = cell.y > 0.
+        ? mix(SHALLOW_COLOR, DEEP_COLOR, clamp(cell.y / depth_full, 0., 1.))
+        : colorMap(cell.x);
+    color = mix(color, GRID_COLOR, gridLine(column, float(grid_step)) * GRID_OPACITY);
+
+    if (cursor.x >= 0 && (texel.x == cursor.x || texel.y == cursor.y)) {
+        float center = texel == cursor ? 1. : 0.5;
+        color = mix(color, CURSOR_COLOR, center);
+    }
+    Color = vec4(color, 1.);
+}
\ No newline at end of file
@@ -0,0 +1,10 @@
+#version 410 core
+
+in vec4 vertColor;
+
+out vec4 Color;
+
+void main()
+{
+    Color = vertColor;
+}
\ No newline at end of file
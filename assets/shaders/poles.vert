@@ -0,0 +1,15 @@
+#version 410 core
+
+layout (location = 0) in vec3 Position;
+layout (location = 1) in vec4 Color;
+
+out vec4 vertColor;
+
+#include "include/camera.glsl"
+
+void main()
+{
+    gl_Position = mvp_transform * vec4(Position, 1.0);
+    gl_PointSize = 8.;
+    vertColor = Color;
+}
\ No newline at end of file
@@ -0,0 +1,41 @@
+#version 410 core
+
+in vec2 passUV;
+
+out vec4 Color;
+
+uniform sampler2D cells;        // (kind, energy): kind 0 empty, 1 ground, 2 water
+uniform int energy_colors;
+
+const float ENERGY_MAX = 40000.;    // energy of wave particles
+const vec4 EMPTY_COLOR = vec4(0.05, 0.05, 0.1, 0.6);
+const vec4 GROUND_COLOR = vec4(0.45, 0.33, 0.2, 1.);
+const vec4 PROFILE_COLOR = vec4(0.85, 0.7, 0.4, 1.);
+const vec4 WATER_COLOR = vec4(0.15, 0.4, 0.95, 1.);
+
+vec3 heat(in float t) {
+    vec3 cold = vec3(0.05, 0.1, 0.5);
+    vec3 warm = vec3(1., 0.85, 0.2);
+    vec3 hot = vec3(0.9, 0.1, 0.05);
+    return t < 0.5 ? mix(cold, warm, t * 2.) : mix(warm, hot, t * 2. - 1.);
+}
+
+void main() {
+    ivec2 size = textureSize(cells, 0);
+    ivec2 texel = min(ivec2(passUV * vec2(size)), size - 1);
+    vec2 cell = texelFetch(cells, texel, 0).xy;
+    int kind = int(cell.x + 0.5);
+
+    if (kind == 1) {
+        bool top = texel.y + 1 >= size.y || int(texelFetch(cells, texel + ivec2(0, 1), 0).x + 0.5) != 1;
+        Color = top ? PROFILE_COLOR : GROUND_COLOR;
+    }
+    else if (kind == 2) {
+        Color = energy_colors != 0
+            ? vec4(heat(clamp(log(1. + cell.y) / log(1. + ENERGY_MAX), 0., 1.)), 1.)
+            : WATER_COLOR;
+    }
+    else {
+        Color = EMPTY_COLOR;
+    }
+}
\ No newline at end of file
@@ -0,0 +1,11 @@
+#version 410 core
+
+layout (location = 0) in vec4 Position;    // (x, y) in NDC, (u, v) in the slice
+
+out vec2 passUV;
+
+void main()
+{
+    gl_Position = vec4(Position.xy, 0., 1.);
+    passUV = Position.zw;
+}
\ No newline at end of file
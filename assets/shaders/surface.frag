@@ -1,9 +1,34 @@
 #version 410 core
 
-in vec4 passColor;
+in vec3 passNormal;
+in vec3 passPosition;
 
 out vec4 Color;
 
+#include "include/lighting.glsl"
+#include "include/color_map.glsl"
+
+uniform float contour_interval;     // 0 disables contours
+
+const vec3 SPECULAR_COLOR = vec3(0.3, 0.3, 0.3);
+const float SHININESS = 32.;
+const vec3 CONTOUR_COLOR = vec3(0.05, 0.05, 0.05);
+const float CONTOUR_OPACITY = 0.7;
+
+// 1 on isolines of height, antialiased to one pixel width
+float contour(in float height) {
+    if (contour_interval <= 0.) {
+        return 0.;
+    }
+    float level = height / contour_interval;
+    float dist = abs(fract(level - 0.5) - 0.5);
+    return 1. - smoothstep(0., fwidth(level), dist);
+}
+
 void main() {
-    Color = passColor;
+    vec3 normal = normalize(passNormal);
+    vec3 color = sunLight(colorMap(passPosition.y), normal, passPosition, SPECULAR_COLOR, SHININESS);
+
+    color = mix(color, CONTOUR_COLOR, contour(passPosition.y) * CONTOUR_OPACITY);
+    Color = vec4(color, 1.);
 }
\ No newline at end of file
@@ -0,0 +1,13 @@
+#version 410 core
+
+in vec2 passUv;
+in vec4 passColor;
+
+out vec4 Color;
+
+uniform sampler2D glyphs;
+
+void main()
+{
+    Color = vec4(passColor.rgb, passColor.a * texture(glyphs, passUv).r);
+}
\ No newline at end of file
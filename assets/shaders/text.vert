@@ -0,0 +1,17 @@
+#version 410 core
+
+layout (location = 0) in vec4 PositionUv;  // position in pixels from top left corner, atlas coordinates
+layout (location = 1) in vec4 Color;
+
+out vec2 passUv;
+out vec4 passColor;
+
+uniform vec2 viewport_size;
+
+void main()
+{
+    vec2 ndc = PositionUv.xy / viewport_size * 2. - 1.;
+    gl_Position = vec4(ndc.x, -ndc.y, 0., 1.);
+    passUv = PositionUv.zw;
+    passColor = Color;
+}
\ No newline at end of file
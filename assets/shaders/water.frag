@@ -1,10 +1,52 @@
 #version 410 core
 
 in vec4 vertColor;
+flat in int passParticle;
 
 out vec4 Color;
 
+uniform int color_mode;                 // 0 depth, 1 energy, 2 direction, 3 origin, 4 age
+uniform samplerBuffer particle_data;    // (energy, direction, origin, born) per particle
+uniform float step;
+
+const float ENERGY_MAX = 40000.;        // energy of wave particles
+const float AGE_MAX = 500.;
+const float ALPHA = 0.85;
+
+// North, South, East, West
+const vec3 DIRECTION_COLORS[4] = vec3[4](
+    vec3(0.9, 0.2, 0.2), vec3(0.2, 0.8, 0.3), vec3(0.95, 0.85, 0.2), vec3(0.7, 0.3, 0.9));
+// Level, Rain, Wave, Placed
+const vec3 ORIGIN_COLORS[4] = vec3[4](
+    vec3(0.1, 0.3, 0.9), vec3(0.6, 0.9, 1.), vec3(1., 0.55, 0.1), vec3(0.3, 0.85, 0.3));
+
+vec3 heat(in float t) {
+    vec3 cold = vec3(0.05, 0.1, 0.5);
+    vec3 warm = vec3(1., 0.85, 0.2);
+    vec3 hot = vec3(0.9, 0.1, 0.05);
+    return t < 0.5 ? mix(cold, warm, t * 2.) : mix(warm, hot, t * 2. - 1.);
+}
+
 void main()
 {
-    Color = vertColor;
+    if (color_mode == 0) {
+        Color = vertColor;
+        return;
+    }
+
+    vec4 data = texelFetch(particle_data, passParticle);
+    vec3 color;
+    if (color_mode == 1) {
+        color = heat(clamp(log(1. + data.x) / log(1. + ENERGY_MAX), 0., 1.));
+    }
+    else if (color_mode == 2) {
+        color = DIRECTION_COLORS[int(data.y)];
+    }
+    else if (color_mode == 3) {
+        color = ORIGIN_COLORS[int(data.z)];
+    }
+    else {
+        color = mix(vec3(1., 1., 1.), vec3(0., 0.1, 0.6), clamp((step - data.w) / AGE_MAX, 0., 1.));
+    }
+    Color = vec4(color, ALPHA);
 }
\ No newline at end of file
@@ -1,13 +1,27 @@
 #version 410 core
 
-layout (location = 0) in vec3 Position;
+layout (location = 0) in vec2 Corner;     // (x, z) of the quad corner in cells
+layout (location = 1) in uvec3 Cell;      // (x, y, z) of the particle, one per instance
 
 out vec4 vertColor;
+flat out int passParticle;
 
-uniform mat4 mvp_transform;
+#include "include/camera.glsl"
+uniform sampler2D ground_heights;
+uniform vec2 cell_size;                   // (x and z, y) size of a grid cell
+
+const vec4 SHALLOW = vec4(0.35, 0.75, 1., 0.45);
+const vec4 DEEP = vec4(0., 0.1, 0.6, 0.85);
+const float DEEP_LEVEL = 0.3;
 
 void main()
 {
+    vec3 Position = vec3(-1., 0., -1.) + (vec3(Cell) + vec3(Corner.x, 0., Corner.y)) * cell_size.xyx;
     gl_Position = mvp_transform * vec4(Position, 1.0);
-    vertColor = vec4(0., 0., 1., 0.9);
+    gl_PointSize = 2.;
+
+    float ground = texture(ground_heights, (Position.xz + 1.) / 2.).r;
+    float depth = clamp((Position.y - ground) / DEEP_LEVEL, 0., 1.);
+    vertColor = mix(SHALLOW, DEEP, depth);
+    passParticle = gl_InstanceID;
 }
\ No newline at end of file
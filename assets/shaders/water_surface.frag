@@ -0,0 +1,25 @@
+#version 410 core
+
+in vec4 passColor;
+in vec3 passNormal;
+in vec3 passPosition;
+in float passWet;
+
+out vec4 Color;
+
+#include "include/lighting.glsl"
+
+const vec3 SPECULAR_COLOR = vec3(0.6, 0.6, 0.6);
+const float SHININESS = 64.;
+
+void main()
+{
+    if (passWet < 0.5) {
+        discard;
+    }
+
+    vec3 normal = normalize(passNormal);
+    vec3 color = sunLight(passColor.rgb, normal, passPosition, SPECULAR_COLOR, SHININESS);
+
+    Color = vec4(color, passColor.a);
+}
\ No newline at end of file
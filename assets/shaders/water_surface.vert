@@ -0,0 +1,29 @@
+#version 410 core
+
+layout (location = 0) in vec3 Position;
+layout (location = 1) in vec4 NormalWet;
+
+out vec4 passColor;
+out vec3 passNormal;
+out vec3 passPosition;
+out float passWet;
+
+#include "include/camera.glsl"
+uniform sampler2D ground_heights;
+
+const vec4 SHALLOW = vec4(0.35, 0.75, 1., 0.45);
+const vec4 DEEP = vec4(0., 0.1, 0.6, 0.85);
+const float DEEP_LEVEL = 0.3;
+
+void main()
+{
+    gl_Position = mvp_transform * vec4(Position, 1.0);
+    gl_PointSize = 2.;
+    passNormal = NormalWet.xyz;
+    passPosition = Position;
+    passWet = NormalWet.w;
+
+    float ground = texture(ground_heights, (Position.xz + 1.) / 2.).r;
+    float depth = clamp((Position.y - ground) / DEEP_LEVEL, 0., 1.);
+    passColor = mix(SHALLOW, DEEP, depth);
+}
\ No newline at end of file
@@ -1,5 +1,6 @@
 use gl;
-use gl::types::GLuint;
+use gl::types::{GLenum, GLuint};
+use std::ops::Range;
 
 pub trait BufferType {
     const BUFFER_TYPE: gl::types::GLuint;
@@ -15,8 +16,20 @@ impl BufferType for BufferTypeElementArray {
     const BUFFER_TYPE: GLuint = gl::ELEMENT_ARRAY_BUFFER;
 }
 
+pub struct BufferTypeUniform;
+impl BufferType for BufferTypeUniform {
+    const BUFFER_TYPE: GLuint = gl::UNIFORM_BUFFER;
+}
+
+pub struct BufferTypeTexture;
+impl BufferType for BufferTypeTexture {
+    const BUFFER_TYPE: GLuint = gl::TEXTURE_BUFFER;
+}
+
 pub type ArrayBuffer = Buffer<BufferTypeArray>;
 pub type ElementArrayBuffer = Buffer<BufferTypeElementArray>;
+pub type UniformBuffer = Buffer<BufferTypeUniform>;
+pub type TextureBuffer = Buffer<BufferTypeTexture>;
 
 pub struct Buffer<B: BufferType> {
     gl: gl::Gl,
@@ -40,6 +53,10 @@ impl<B: BufferType> Buffer<B> {
         }
     }
 
+    pub fn id(&self) -> GLuint {
+        self.vbo
+    }
+
     pub fn bind(&self) {
         unsafe {
             self.gl.BindBuffer(B::BUFFER_TYPE, self.vbo);
@@ -52,6 +69,13 @@ impl<B: BufferType> Buffer<B> {
         }
     }
 
+    // Attaches the whole buffer to an indexed binding point of its target
+    pub fn bind_base(&self, index: GLuint) {
+        unsafe {
+            self.gl.BindBufferBase(B::BUFFER_TYPE, index, self.vbo);
+        }
+    }
+
     pub fn static_draw_data<T>(&self, data: &[T]) {
         unsafe {
             self.gl.BufferData(
@@ -74,6 +98,38 @@ impl<B: BufferType> Buffer<B> {
         }
     }
 
+    // Writes `data` from element `offset` on, the storage has to have room for it
+    pub fn sub_data<T>(&self, offset: usize, data: &[T]) {
+        unsafe {
+            self.gl.BufferSubData(
+                B::BUFFER_TYPE,
+                (offset * ::std::mem::size_of::<T>()) as gl::types::GLintptr,
+                ::std::mem::size_of_val(data) as gl::types::GLsizeiptr,
+                data.as_ptr() as *const gl::types::GLvoid,
+            );
+        }
+    }
+
+    // New storage for `count` elements with undefined contents. The old one is released once
+    // draw calls still reading it are done, so writing right after does not wait for them
+    pub fn orphan<T>(&self, count: usize, usage: GLenum) {
+        unsafe {
+            self.gl.BufferData(
+                B::BUFFER_TYPE,
+                (count * ::std::mem::size_of::<T>()) as gl::types::GLsizeiptr,
+                ::std::ptr::null(),
+                usage,
+            );
+        }
+    }
+
+    // For data replaced as a whole every frame. Persistently mapped buffers would avoid the copy,
+    // but they need GL 4.4 (ARB_buffer_storage) and the context is 4.1
+    pub fn stream_draw_data<T>(&self, data: &[T]) {
+        self.orphan::<T>(data.len(), gl::STREAM_DRAW);
+        self.sub_data(0, data);
+    }
+
     pub fn set_elem_count(&mut self, count: usize) {
         self.elem_count = count;
     }
@@ -91,6 +147,133 @@ impl<B: BufferType> Drop for Buffer<B> {
     }
 }
 
+// Clean elements between two changed ones which are uploaded anyway to save a call
+const DIRTY_MERGE_GAP: usize = 64;
+
+// Element ranges changed since the last upload. Marks are expected in increasing order,
+// one out of order merges everything into a single range
+#[derive(Default)]
+pub struct DirtyRanges {
+    ranges: Vec<Range<usize>>,
+}
+
+impl DirtyRanges {
+    pub fn mark(&mut self, index: usize) {
+        self.mark_range(index..index + 1);
+    }
+
+    pub fn mark_range(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let first_start = self.ranges.first().map_or(range.start, |first| first.start);
+        match self.ranges.last_mut() {
+            None => self.ranges.push(range),
+            Some(last) if range.start > last.end + DIRTY_MERGE_GAP => self.ranges.push(range),
+            Some(last) if range.end + DIRTY_MERGE_GAP >= last.start => {
+                last.start = last.start.min(range.start);
+                last.end = last.end.max(range.end);
+            },
+            Some(last) => {
+                let end = last.end;
+                self.ranges.clear();
+                self.ranges.push(first_start.min(range.start)..end);
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn take(&mut self) -> Vec<Range<usize>> {
+        ::std::mem::take(&mut self.ranges)
+    }
+}
+
+// Array kept both in memory and in a GPU buffer, `upload` sends only elements changed since the
+// last one. Storage grows by doubling, it is orphaned and written as a whole then
+pub struct SyncedBuffer<B: BufferType, T> {
+    buffer: Buffer<B>,
+    data: Vec<T>,
+    capacity: usize,        // elements the GPU storage has room for
+    dirty: DirtyRanges,
+}
+
+impl<B: BufferType, T: Copy + PartialEq> SyncedBuffer<B, T> {
+    pub fn new(gl: &gl::Gl) -> SyncedBuffer<B, T> {
+        SyncedBuffer { buffer: Buffer::new(gl), data: Vec::new(), capacity: 0, dirty: DirtyRanges::default() }
+    }
+
+    // Element count of the buffer is the length at the last upload
+    pub fn buffer(&self) -> &Buffer<B> {
+        &self.buffer
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    // `index` equal to the length appends, an element set to the value it has is not uploaded again
+    pub fn set(&mut self, index: usize, value: T) {
+        if index == self.data.len() {
+            self.data.push(value);
+        }
+        else if self.data[index] != value {
+            self.data[index] = value;
+        }
+        else {
+            return;
+        }
+        self.dirty.mark(index);
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.set(self.data.len(), value);
+    }
+
+    // Elements past `len` are left on the GPU, they are not drawn with the new element count
+    pub fn truncate(&mut self, len: usize) {
+        self.data.truncate(len);
+    }
+
+    // Replaces the contents, elements equal to the ones they replace are not uploaded
+    pub fn assign<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        let mut len = 0;
+        for (index, value) in values.into_iter().enumerate() {
+            self.set(index, value);
+            len = index + 1;
+        }
+        self.truncate(len);
+    }
+
+    pub fn upload(&mut self) {
+        self.buffer.bind();
+        if self.data.len() > self.capacity {
+            self.capacity = self.data.len().next_power_of_two();
+            self.buffer.orphan::<T>(self.capacity, gl::DYNAMIC_DRAW);
+            self.buffer.sub_data(0, &self.data);
+            self.dirty.take();
+        }
+        for range in self.dirty.take() {
+            let range = range.start..range.end.min(self.data.len());
+            if !range.is_empty() {
+                self.buffer.sub_data(range.start, &self.data[range]);
+            }
+        }
+        self.buffer.unbind();
+        self.buffer.set_elem_count(self.data.len());
+    }
+}
+
 pub struct VertexArray {
     gl: gl::Gl,
     vao: gl::types::GLuint,
@@ -127,4 +310,45 @@ impl Drop for VertexArray {
             self.gl.DeleteVertexArrays(1, &mut self.vao);
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marked(indices: &[usize]) -> Vec<Range<usize>> {
+        let mut dirty = DirtyRanges::default();
+        for &index in indices {
+            dirty.mark(index);
+        }
+        dirty.take()
+    }
+
+    #[test]
+    fn in_order_marks_far_apart_stay_separate() {
+        assert_eq!(marked(&[0, 100, 300]), vec![0..1, 100..101, 300..301]);
+    }
+
+    #[test]
+    fn marks_within_gap_are_merged() {
+        assert_eq!(marked(&[0, 1, 50, 50 + DIRTY_MERGE_GAP]), vec![0..51 + DIRTY_MERGE_GAP]);
+    }
+
+    #[test]
+    fn out_of_order_marks() {
+        // Close to the last range it is extended back
+        assert_eq!(marked(&[0, 200, 190]), vec![0..1, 190..201]);
+        // Otherwise everything becomes one range
+        assert_eq!(marked(&[100, 300, 10]), vec![10..301]);
+    }
+
+    #[test]
+    fn take_empties_ranges() {
+        let mut dirty = DirtyRanges::default();
+        dirty.mark_range(5..5);
+        assert!(dirty.is_empty());
+        dirty.mark_range(5..10);
+        assert_eq!(dirty.take(), vec![5..10]);
+        assert!(dirty.is_empty());
+    }
 }
\ No newline at end of file
@@ -1,73 +1,150 @@
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-#[repr(C, packed)]
-pub struct f32_f32_f32 {
-    pub d0: f32,
-    pub d1: f32,
-    pub d2: f32,
+use gl::types::{GLenum, GLint, GLuint, GLvoid};
+
+// Type of a vertex struct field, #[derive(VertexAttribPointers)] calls it for every field
+pub trait VertexAttrib {
+    unsafe fn vertex_attrib_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize);
+}
+
+// Components are converted to floats in the shader, integers as they are
+unsafe fn float_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize, size: GLint, kind: GLenum) {
+    gl.EnableVertexAttribArray(location as GLuint);
+    gl.VertexAttribPointer(location as GLuint, size, kind, gl::FALSE, stride as GLint, offset as *const GLvoid);
+}
+
+// Unsigned integers are mapped to [0; 1]
+unsafe fn normalized_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize, size: GLint, kind: GLenum) {
+    gl.EnableVertexAttribArray(location as GLuint);
+    gl.VertexAttribPointer(location as GLuint, size, kind, gl::TRUE, stride as GLint, offset as *const GLvoid);
+}
+
+// For int, ivecN, uint and uvecN shader inputs
+unsafe fn integer_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize, size: GLint, kind: GLenum) {
+    gl.EnableVertexAttribArray(location as GLuint);
+    gl.VertexAttribIPointer(location as GLuint, size, kind, stride as GLint, offset as *const GLvoid);
 }
 
-impl From<(f32, f32, f32)> for f32_f32_f32 {
-    fn from(other: (f32, f32, f32)) -> Self {
-        f32_f32_f32::new(other.0, other.1, other.2)
+impl VertexAttrib for f32 {
+    unsafe fn vertex_attrib_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize) {
+        float_pointer(gl, stride, location, offset, 1, gl::FLOAT);
     }
 }
 
-impl f32_f32_f32 {
-    pub fn new(d0: f32, d1: f32, d2: f32) -> f32_f32_f32 {
-        f32_f32_f32 {
-            d0, d1, d2
-        }
+impl VertexAttrib for i32 {
+    unsafe fn vertex_attrib_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize) {
+        integer_pointer(gl, stride, location, offset, 1, gl::INT);
     }
+}
 
-    pub unsafe fn vertex_attrib_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize) {
-        gl.EnableVertexAttribArray(location as gl::types::GLuint);
-        gl.VertexAttribPointer(
-            location as gl::types::GLuint,
-            3,
-            gl::FLOAT,
-            gl::FALSE,
-            stride as gl::types::GLint,
-            offset as *const gl::types::GLvoid
-        );
+impl VertexAttrib for u32 {
+    unsafe fn vertex_attrib_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize) {
+        integer_pointer(gl, stride, location, offset, 1, gl::UNSIGNED_INT);
     }
 }
 
+// IEEE half precision float, read by the shader as a regular float
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-#[repr(C, packed)]
-pub struct f32_f32_f32_f32 {
-    pub d0: f32,
-    pub d1: f32,
-    pub d2: f32,
-    pub d3: f32,
-}
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(transparent)]
+pub struct f16(pub u16);
+
+impl f16 {
+    // Rounds to nearest even, out of range values become infinities
+    pub fn from_f32(value: f32) -> f16 {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32;
+        let mantissa = bits & 0x7f_ffff;
 
-impl From<(f32, f32, f32, f32)> for f32_f32_f32_f32 {
-    fn from(other: (f32, f32, f32, f32)) -> Self {
-        f32_f32_f32_f32::new(other.0, other.1, other.2, other.3)
+        if exponent == 0xff {
+            let nan = if mantissa != 0 { 0x200 } else { 0 };
+            return f16(sign | 0x7c00 | nan);
+        }
+        let exponent = exponent - 127 + 15;
+        if exponent >= 0x1f {
+            return f16(sign | 0x7c00);
+        }
+        if exponent <= 0 {
+            // Subnormal half, too small values are zero
+            if exponent < -10 {
+                return f16(sign);
+            }
+            let mantissa = mantissa | 0x80_0000;
+            let shift = (14 - exponent) as u32;
+            let rounded = (mantissa + (1 << (shift - 1)) - 1 + ((mantissa >> shift) & 1)) >> shift;
+            return f16(sign | rounded as u16);
+        }
+        // Mantissa carry goes to exponent, up to infinity
+        let rounded = mantissa + 0xfff + ((mantissa >> 13) & 1);
+        let half = (((exponent as u32) << 10) + (rounded >> 13)).min(0x7c00);
+        f16(sign | half as u16)
     }
 }
 
-impl f32_f32_f32_f32 {
-    pub fn new(d0: f32, d1: f32, d2: f32, d3: f32) -> Self {
-        f32_f32_f32_f32 {
-            d0, d1, d2, d3,
-        }
+impl From<f32> for f16 {
+    fn from(other: f32) -> Self {
+        f16::from_f32(other)
     }
+}
 
-    pub unsafe fn vertex_attrib_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize) {
-        gl.EnableVertexAttribArray(location as gl::types::GLuint);
-        gl.VertexAttribPointer(
-            location as gl::types::GLuint,
-            4,
-            gl::FLOAT,
-            gl::FALSE,
-            stride as gl::types::GLint,
-            offset as *const gl::types::GLvoid,
-        )
+impl VertexAttrib for f16 {
+    unsafe fn vertex_attrib_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize) {
+        float_pointer(gl, stride, location, offset, 1, gl::HALF_FLOAT);
     }
 }
 
+macro_rules! replace_type {
+    ($_field:ident, $ty:ty) => { $ty };
+}
+
+// Packed struct of `d0, d1, ...` components stored as `$component`, built from a tuple of `$from`.
+// `$pointer` is one of the functions above, `$kind` is the GL component type
+macro_rules! vertex_data {
+    ($name:ident, $component:ty, $from:ty, $pointer:ident, $kind:expr, $($field:ident),+) => {
+        #[allow(non_camel_case_types)]
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        #[repr(C, packed)]
+        pub struct $name {
+            $(pub $field: $component,)+
+        }
+
+        impl $name {
+            pub fn new($($field: $component),+) -> $name {
+                $name { $($field),+ }
+            }
+        }
+
+        impl From<($(replace_type!($field, $from),)+)> for $name {
+            fn from(other: ($(replace_type!($field, $from),)+)) -> Self {
+                let ($($field,)+) = other;
+                $name { $($field: $field.into()),+ }
+            }
+        }
+
+        impl VertexAttrib for $name {
+            unsafe fn vertex_attrib_pointer(gl: &gl::Gl, stride: usize, location: usize, offset: usize) {
+                const SIZE: GLint = [$(stringify!($field)),+].len() as GLint;
+                $pointer(gl, stride, location, offset, SIZE, $kind);
+            }
+        }
+    };
+}
+
+vertex_data!(f32_f32, f32, f32, float_pointer, gl::FLOAT, d0, d1);
+vertex_data!(f32_f32_f32, f32, f32, float_pointer, gl::FLOAT, d0, d1, d2);
+vertex_data!(f32_f32_f32_f32, f32, f32, float_pointer, gl::FLOAT, d0, d1, d2, d3);
+
+// Half floats are made from f32 tuples
+vertex_data!(f16_f16, f16, f32, float_pointer, gl::HALF_FLOAT, d0, d1);
+vertex_data!(f16_f16_f16, f16, f32, float_pointer, gl::HALF_FLOAT, d0, d1, d2);
+vertex_data!(f16_f16_f16_f16, f16, f32, float_pointer, gl::HALF_FLOAT, d0, d1, d2, d3);
 
+// Normalized colours, 255 or 65535 is 1. in the shader
+vertex_data!(u8_u8_u8_u8_norm, u8, u8, normalized_pointer, gl::UNSIGNED_BYTE, d0, d1, d2, d3);
+vertex_data!(u16_u16_u16_u16_norm, u16, u16, normalized_pointer, gl::UNSIGNED_SHORT, d0, d1, d2, d3);
 
+vertex_data!(i32_i32, i32, i32, integer_pointer, gl::INT, d0, d1);
+vertex_data!(i32_i32_i32, i32, i32, integer_pointer, gl::INT, d0, d1, d2);
+vertex_data!(i32_i32_i32_i32, i32, i32, integer_pointer, gl::INT, d0, d1, d2, d3);
+vertex_data!(u32_u32, u32, u32, integer_pointer, gl::UNSIGNED_INT, d0, d1);
+vertex_data!(u32_u32_u32, u32, u32, integer_pointer, gl::UNSIGNED_INT, d0, d1, d2);
+vertex_data!(u32_u32_u32_u32, u32, u32, integer_pointer, gl::UNSIGNED_INT, d0, d1, d2, d3);
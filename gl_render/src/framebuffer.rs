@@ -0,0 +1,142 @@
+use gl::types::{GLenum, GLint, GLuint};
+use crate::texture::{Texture2D, TextureFormat};
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Framebuffer is incomplete, status: {:#x}", status)]
+    Incomplete { status: GLenum },
+    #[fail(display = "Only colour can be read from a framebuffer, not {:?}", format)]
+    NotColorFormat { format: TextureFormat },
+}
+
+pub struct Renderbuffer {
+    gl: gl::Gl,
+    id: GLuint,
+    format: TextureFormat,
+}
+
+impl Renderbuffer {
+    pub fn new(gl: &gl::Gl, format: TextureFormat, width: i32, height: i32) -> Renderbuffer {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl.GenRenderbuffers(1, &mut id);
+        }
+        let renderbuffer = Renderbuffer { gl: gl.clone(), id, format };
+        renderbuffer.resize(width, height);
+        renderbuffer
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    // Contents are lost, framebuffers it is attached to keep it
+    pub fn resize(&self, width: i32, height: i32) {
+        unsafe {
+            self.gl.BindRenderbuffer(gl::RENDERBUFFER, self.id);
+            self.gl.RenderbufferStorage(gl::RENDERBUFFER, self.format.internal_format(), width, height);
+            self.gl.BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.DeleteRenderbuffers(1, &self.id);
+        }
+    }
+}
+
+// Attachments are not owned, they have to outlive drawing into the framebuffer
+pub struct Framebuffer {
+    gl: gl::Gl,
+    id: GLuint,
+}
+
+impl Framebuffer {
+    pub fn new(gl: &gl::Gl) -> Framebuffer {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl.GenFramebuffers(1, &mut id);
+        }
+        Framebuffer { gl: gl.clone(), id }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            self.gl.BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        }
+    }
+
+    pub fn unbind(&self) {
+        unsafe {
+            self.gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    // attachment is gl::COLOR_ATTACHMENTi, gl::DEPTH_ATTACHMENT or gl::STENCIL_ATTACHMENT
+    pub fn attach_renderbuffer(&self, attachment: GLenum, renderbuffer: &Renderbuffer) {
+        self.bind();
+        unsafe {
+            self.gl.FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment, gl::RENDERBUFFER, renderbuffer.id());
+        }
+        self.unbind();
+    }
+
+    pub fn attach_texture(&self, attachment: GLenum, texture: &Texture2D) {
+        self.bind();
+        unsafe {
+            self.gl.FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture.id(), 0);
+        }
+        self.unbind();
+    }
+
+    pub fn check(&self) -> Result<(), Error> {
+        self.bind();
+        let status = unsafe { self.gl.CheckFramebufferStatus(gl::FRAMEBUFFER) };
+        self.unbind();
+        match status {
+            gl::FRAMEBUFFER_COMPLETE => Ok(()),
+            status => Err(Error::Incomplete { status }),
+        }
+    }
+
+    // Colour attachment 0 in `format` layout, bottom row first
+    pub fn read_pixels(&self, width: i32, height: i32, format: TextureFormat) -> Result<Vec<u8>, Error> {
+        if format == TextureFormat::Depth24 {
+            return Err(Error::NotColorFormat { format });
+        }
+        let mut pixels: Vec<u8> = vec![0; width as usize * height as usize * format.pixel_size()];
+        unsafe {
+            self.gl.BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            self.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+            self.gl.ReadPixels(0, 0, width, height, format.pixel_format(), format.pixel_type(),
+                               pixels.as_mut_ptr() as *mut gl::types::GLvoid);
+            self.gl.BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+        Ok(pixels)
+    }
+
+    // Copies colour of the (0, 0, width, height) rectangle to the window framebuffer
+    pub fn blit_to_screen(&self, width: GLint, height: GLint) {
+        unsafe {
+            self.gl.BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            self.gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            self.gl.BlitFramebuffer(0, 0, width, height, 0, 0, width, height, gl::COLOR_BUFFER_BIT, gl::NEAREST);
+            self.gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 #[macro_use] extern crate failure;
 extern crate resources;
 extern crate gl_builder as gl;
+extern crate nalgebra as na;
 
 mod shader;
 pub use self::shader::{Shader, Program, Error};
@@ -15,3 +16,9 @@ pub mod data;
 pub mod buffer;
 
 pub mod uniform;
+pub mod uniform_block;
+
+pub mod texture;
+pub mod framebuffer;
+
+pub mod text;
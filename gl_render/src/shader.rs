@@ -1,7 +1,11 @@
 use gl;
 use std;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ffi::{CString, CStr};
+use std::time::SystemTime;
 use resources::{self, Resources};
+use crate::uniform::{Uniform, Uniforms};
 
 #[derive(Debug, Fail)]
 pub enum Error {
@@ -13,11 +17,30 @@ pub enum Error {
     CompileError { name: String, message: String },
     #[fail(display = "Failed to link program {}: {}", name, message)]
     LinkError { name: String, message: String },
+    #[fail(display = "No shader stages found for program {}", name)]
+    NoShaderStages { name: String },
+    #[fail(display = "Failed to preprocess shader {}: {}", name, message)]
+    IncludeError { name: String, message: String },
 }
 
+// Shader stages by resource extension, in pipeline order
+const SHADER_STAGES: [(&str, gl::types::GLenum); 6] = [
+    (".vert", gl::VERTEX_SHADER),
+    (".tesc", gl::TESS_CONTROL_SHADER),
+    (".tese", gl::TESS_EVALUATION_SHADER),
+    (".geom", gl::GEOMETRY_SHADER),
+    (".frag", gl::FRAGMENT_SHADER),
+    (".comp", gl::COMPUTE_SHADER),
+];
+
 pub struct Program {
     gl: gl::Gl,
     id: gl::types::GLuint,
+    uniforms: HashMap<String, gl::types::GLint>,    // locations of active uniforms, looked up once on link
+    name_buffer: RefCell<String>,                   // `{prefix}_{name}` of the uniform being set
+    name: String,                                   // resource name of programs loaded by from_res
+    sources: Vec<String>,                           // resources the program is built from, includes too
+    modified: Option<SystemTime>,                   // newest of the sources when it was last (re)loaded
 }
 
 impl Program {
@@ -31,22 +54,90 @@ impl Program {
         }
     }
 
+    pub fn uniform_location(&self, name: &str) -> Option<gl::types::GLint> {
+        self.uniforms.get(name).copied()
+    }
+
+    // Uniforms optimized out by the driver are skipped
+    pub fn set_uniform<T: Uniform + ?Sized>(&self, name: &str, value: &T) {
+        if let Some(location) = self.uniform_location(name) {
+            self.use_it();
+            value.set(&self.gl, location);
+        }
+    }
+
+    // Sets `{prefix}_{name}`, or just `name` with empty prefix
+    pub fn set_prefixed_uniform<T: Uniform + ?Sized>(&self, prefix: &str, name: &str, value: &T) {
+        if prefix.is_empty() {
+            return self.set_uniform(name, value);
+        }
+        let mut full_name = self.name_buffer.borrow_mut();
+        full_name.clear();
+        full_name.push_str(prefix);
+        full_name.push('_');
+        full_name.push_str(name);
+        self.set_uniform(&full_name, value);
+    }
+
+    // Block is read from the uniform buffer at `binding`, programs without the block are left as they are
+    pub fn bind_uniform_block(&self, name: &str, binding: gl::types::GLuint) {
+        let name_cstr = match CString::new(name) {
+            Ok(name_cstr) => name_cstr,
+            Err(_) => return,
+        };
+        unsafe {
+            let index = self.gl.GetUniformBlockIndex(self.id, name_cstr.as_ptr());
+            if index != gl::INVALID_INDEX {
+                self.gl.UniformBlockBinding(self.id, index, binding);
+            }
+        }
+    }
+
+    pub fn set_uniforms<T: Uniforms>(&self, value: &T, prefix: &str) {
+        value.set_uniforms(self, prefix);
+    }
+
+    // Links every `{name}.{stage}` resource there is, stages are listed in SHADER_STAGES
     pub fn from_res(gl: &gl::Gl, res: &Resources, name: &str) -> Result<Program, Error> {
-        const POSSIBLE_EXT: [&str; 2] = [
-            ".vert",
-            ".frag",
-        ];
-
-        let shaders = POSSIBLE_EXT.iter()
-            .map(|file_extension| {
-                Shader::from_res(gl, res, &format!("{}{}", name, file_extension))
+        let mut sources = Vec::new();
+        let shaders = SHADER_STAGES.iter()
+            .map(|(file_extension, _)| format!("{}{}", name, file_extension))
+            .filter(|stage_name| res.exists(stage_name))
+            .map(|stage_name| {
+                let mut included = Vec::new();
+                let shader = Shader::load(gl, res, &stage_name, &mut included);
+                sources.append(&mut included);
+                shader
             })
             .collect::<Result<Vec<Shader>, Error>>()?;
+        if shaders.is_empty() {
+            return Err(Error::NoShaderStages { name: name.into() });
+        }
+        sources.sort();
+        sources.dedup();
 
-        Program::from_shaders(gl, &shaders[..]).map_err(|message| Error::LinkError {
+        let mut program = Program::from_shaders(gl, &shaders[..]).map_err(|message| Error::LinkError {
             name: name.into(),
             message,
-        })
+        })?;
+        program.modified = newest_modified(res, &sources);
+        program.name = name.into();
+        program.sources = sources;
+        Ok(program)
+    }
+
+    // Relinks a program loaded by from_res when one of its sources in `res` got newer than it was
+    // at the last attempt. `res` may be another copy of the resources, e.g. the source tree.
+    // On failure the old program stays and the error is returned once, until the next change.
+    // Uniform values and block bindings of the old program are not carried over
+    pub fn reload_if_changed(&mut self, res: &Resources) -> Result<bool, Error> {
+        let modified = newest_modified(res, &self.sources);
+        if modified <= self.modified {
+            return Ok(false);
+        }
+        self.modified = modified;
+        *self = Program::from_res(&self.gl, res, &self.name)?;
+        Ok(true)
     }
 
     pub fn from_shaders(gl: &gl::Gl, shaders: &[Shader]) -> Result<Program, String> {
@@ -82,10 +173,59 @@ impl Program {
             unsafe { gl.DetachShader(program_id, shader.id()); }
         }
 
-        Ok(Program { gl: gl.clone(), id: program_id })
+        let uniforms = active_uniforms(gl, program_id);
+        Ok(Program {
+            gl: gl.clone(),
+            id: program_id,
+            uniforms,
+            name_buffer: RefCell::new(String::new()),
+            name: String::new(),
+            sources: Vec::new(),
+            modified: None,
+        })
     }
 }
 
+fn newest_modified(res: &Resources, sources: &[String]) -> Option<SystemTime> {
+    sources.iter().filter_map(|source| res.modified(source)).max()
+}
+
+// Arrays are found both by `name[0]` and by `name`, members of uniform blocks have no location
+fn active_uniforms(gl: &gl::Gl, program_id: gl::types::GLuint) -> HashMap<String, gl::types::GLint> {
+    let mut count: gl::types::GLint = 0;
+    let mut max_len: gl::types::GLint = 0;
+    unsafe {
+        gl.GetProgramiv(program_id, gl::ACTIVE_UNIFORMS, &mut count);
+        gl.GetProgramiv(program_id, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_len);
+    }
+
+    let mut uniforms = HashMap::new();
+    let mut buffer: Vec<u8> = vec![0; max_len.max(1) as usize];
+    for index in 0..count as gl::types::GLuint {
+        let mut len: gl::types::GLsizei = 0;
+        let mut size: gl::types::GLint = 0;
+        let mut kind: gl::types::GLenum = 0;
+        unsafe {
+            gl.GetActiveUniform(program_id, index, buffer.len() as gl::types::GLsizei, &mut len, &mut size,
+                                &mut kind, buffer.as_mut_ptr() as *mut gl::types::GLchar);
+        }
+        let name = String::from_utf8_lossy(&buffer[..len as usize]).into_owned();
+        let name_cstr = match CString::new(name.as_str()) {
+            Ok(name_cstr) => name_cstr,
+            Err(_) => continue,
+        };
+        let location = unsafe { gl.GetUniformLocation(program_id, name_cstr.as_ptr()) };
+        if location < 0 {
+            continue;
+        }
+        if let Some(base) = name.strip_suffix("[0]") {
+            uniforms.insert(base.to_owned(), location);
+        }
+        uniforms.insert(name, location);
+    }
+    uniforms
+}
+
 impl Drop for Program {
     fn drop(&mut self) {
         unsafe {
@@ -105,12 +245,12 @@ impl Shader {
     }
 
     pub fn from_res(gl: &gl::Gl, res: &Resources, name: &str) -> Result<Shader, Error> {
-        const POSSIBLE_EXT: [(&str, gl::types::GLenum); 2] = [
-            (".vert", gl::VERTEX_SHADER),
-            (".frag", gl::FRAGMENT_SHADER),
-        ];
+        Shader::load(gl, res, name, &mut Vec::new())
+    }
 
-        let shader_kind = POSSIBLE_EXT.iter()
+    // `included` gets names of all resources the source is made of, the shader itself goes first
+    fn load(gl: &gl::Gl, res: &Resources, name: &str, included: &mut Vec<String>) -> Result<Shader, Error> {
+        let shader_kind = SHADER_STAGES.iter()
             .find(|&&(file_extension, _)| {
                 name.ends_with(file_extension)
             })
@@ -120,13 +260,17 @@ impl Shader {
                 message: "failed to recognize shader extension".to_owned()
             })?;
 
-        let source = res.load_cstring(name)
-            .map_err(|e| Error::ResourceLoadError {
-                name: name.into(),
-                inner: e,
-            })?;
+        let mut source = String::new();
+        include_source(res, name, included, &mut source)?;
+        let source = CString::new(source).map_err(|_| Error::IncludeError {
+            name: name.into(),
+            message: "source contains 0".to_owned(),
+        })?;
 
-        Shader::from_source(gl, &source, shader_kind)
+        Shader::from_source(gl, &source, shader_kind).map_err(|e| match e {
+            Error::CompileError { message, .. } => Error::CompileError { name: name.into(), message },
+            e => e,
+        })
     }
 
     pub fn from_source(
@@ -155,6 +299,60 @@ impl Drop for Shader {
     }
 }
 
+// Appends the resource with `#include "file"` lines replaced by the file, path is relative to the
+// including resource. Every file goes in once, `#line` keeps compiler messages pointing to the
+// original line, with the source string number being the position of the file in `included`
+fn include_source(res: &Resources, name: &str, included: &mut Vec<String>, out: &mut String) -> Result<(), Error> {
+    let index = included.len();
+    included.push(name.to_owned());
+    let source = res.load_cstring(name)
+        .map_err(|e| Error::ResourceLoadError {
+            name: name.into(),
+            inner: e,
+        })?;
+    let include_error = |line: usize, message: &str| Error::IncludeError {
+        name: name.into(),
+        message: format!("line {}: {}", line, message),
+    };
+    let source = source.to_str().map_err(|_| include_error(0, "source is not valid UTF-8"))?;
+
+    for (line_idx, line) in source.lines().enumerate() {
+        let directive = match line.trim().strip_prefix("#include") {
+            Some(directive) => directive.trim(),
+            None => {
+                out.push_str(line);
+                out.push('\n');
+                continue ;
+            }
+        };
+        let file = directive.strip_prefix('"').and_then(|file| file.strip_suffix('"'))
+            .ok_or_else(|| include_error(line_idx + 1, "expected #include \"file\""))?;
+        let path = resolve_include(name, file)
+            .ok_or_else(|| include_error(line_idx + 1, "include path goes above resource root"))?;
+        if included.contains(&path) {
+            continue ;
+        }
+        out.push_str(&format!("#line 1 {}\n", included.len()));
+        include_source(res, &path, included, out)?;
+        out.push_str(&format!("#line {} {}\n", line_idx + 2, index));
+    }
+    Ok(())
+}
+
+// Resource name of `file` included from resource `name`
+fn resolve_include(name: &str, file: &str) -> Option<String> {
+    let mut parts: Vec<&str> = name.split('/').collect();
+    parts.pop();
+    for part in file.split('/') {
+        match part {
+            "." => (),
+            ".." => { parts.pop()?; },
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
 fn shader_from_source(
     gl: &gl::Gl,
     source: &CStr,
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use ab_glyph::{Font, FontVec, ScaleFont, PxScale, point};
+use resources::Resources;
+use crate::{Program, Viewport};
+use crate::buffer::{ArrayBuffer, VertexArray};
+use crate::data::{f32_f32_f32_f32, VertexAttrib};
+use crate::texture::{Texture2D, TextureFilter, TextureFormat};
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Failed to load font {}", name)]
+    ResourceLoad { name: String, #[cause] inner: resources::Error },
+    #[fail(display = "Font {} is not a valid TrueType/OpenType font", name)]
+    FontNotValid { name: String },
+}
+
+// First and last characters put into the atlas, others are drawn as '?'
+const FIRST_CHAR: char = ' ';
+const LAST_CHAR: char = '~';
+const ATLAS_COLUMNS: usize = 16;
+const BACKGROUND_PADDING: f32 = 4.;
+
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    pos_uv: f32_f32_f32_f32,    // position in pixels from top left corner and atlas coordinates
+    color: f32_f32_f32_f32,
+}
+
+impl Vertex {
+    fn vertex_attrib_pointers(gl: &gl::Gl) {
+        let stride = std::mem::size_of::<Self>();
+        unsafe {
+            f32_f32_f32_f32::vertex_attrib_pointer(gl, stride, 0, 0);
+            f32_f32_f32_f32::vertex_attrib_pointer(gl, stride, 1, std::mem::size_of::<f32_f32_f32_f32>());
+        }
+    }
+}
+
+// Atlas cell of a character: (u0, v0, u1, v1)
+type Cell = (f32, f32, f32, f32);
+
+// Screen text drawn from a glyph atlas rasterized once from a font resource.
+// Every character is a cell_width x cell_height quad, so monospace fonts look best
+pub struct Text {
+    gl: gl::Gl,
+    program: Program,
+    texture: Texture2D,
+    vbo: ArrayBuffer,
+    vao: VertexArray,
+    cells: HashMap<char, Cell>,
+    solid: Cell,            // fully covered cell for backgrounds
+    cell_width: f32,
+    cell_height: f32,
+}
+
+impl Text {
+    pub fn new(gl: &gl::Gl, res: &Resources, shader_name: &str, font_name: &str, size: f32) -> Result<Text, failure::Error> {
+        let program = Program::from_res(gl, res, shader_name)?;
+        let data = res.load_bytes(font_name)
+            .map_err(|e| Error::ResourceLoad { name: font_name.into(), inner: e })?;
+        let font = FontVec::try_from_vec(data)
+            .map_err(|_| Error::FontNotValid { name: font_name.into() })?;
+        let scale = PxScale::from(size);
+        let scaled = font.as_scaled(scale);
+
+        let chars: Vec<char> = (FIRST_CHAR..=LAST_CHAR).collect();
+        let cell_width = scaled.h_advance(font.glyph_id('M')).ceil() as usize + 2;
+        let cell_height = scaled.height().ceil() as usize + 2;
+        let rows = (chars.len() + 1).div_ceil(ATLAS_COLUMNS);
+        let (atlas_width, atlas_height) = (ATLAS_COLUMNS * cell_width, rows * cell_height);
+        let mut atlas: Vec<u8> = vec![0; atlas_width * atlas_height];
+
+        let cell_uv = |idx: usize| -> Cell {
+            let (x, y) = ((idx % ATLAS_COLUMNS) * cell_width, (idx / ATLAS_COLUMNS) * cell_height);
+            (x as f32 / atlas_width as f32, y as f32 / atlas_height as f32,
+             (x + cell_width) as f32 / atlas_width as f32, (y + cell_height) as f32 / atlas_height as f32)
+        };
+
+        let mut cells = HashMap::new();
+        for (idx, c) in chars.iter().enumerate() {
+            let (cell_x, cell_y) = ((idx % ATLAS_COLUMNS) * cell_width, (idx / ATLAS_COLUMNS) * cell_height);
+            let glyph = font.glyph_id(*c).with_scale_and_position(scale, point(1., 1. + scaled.ascent()));
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|x, y, coverage| {
+                    let px = bounds.min.x as i32 + x as i32;
+                    let py = bounds.min.y as i32 + y as i32;
+                    if px >= 0 && py >= 0 && (px as usize) < cell_width && (py as usize) < cell_height {
+                        let pixel = &mut atlas[(cell_y + py as usize) * atlas_width + cell_x + px as usize];
+                        *pixel = pixel.saturating_add((coverage * 255.) as u8);
+                    }
+                });
+            }
+            cells.insert(*c, cell_uv(idx));
+        }
+
+        let solid_idx = chars.len();
+        let (solid_x, solid_y) = ((solid_idx % ATLAS_COLUMNS) * cell_width, (solid_idx / ATLAS_COLUMNS) * cell_height);
+        for row in atlas.chunks_mut(atlas_width).skip(solid_y).take(cell_height) {
+            row[solid_x..solid_x + cell_width].iter_mut().for_each(|pixel| *pixel = 255);
+        }
+        // Sample the middle of the solid cell so filtering never reaches its neighbours
+        let solid = cell_uv(solid_idx);
+        let solid = ((solid.0 + solid.2) / 2., (solid.1 + solid.3) / 2., (solid.0 + solid.2) / 2., (solid.1 + solid.3) / 2.);
+
+        let texture = Texture2D::new(gl, TextureFormat::R8, TextureFilter::Nearest);
+        texture.upload(atlas_width as i32, atlas_height as i32, &atlas)?;
+
+        let vbo = ArrayBuffer::new(gl);
+        let vao = VertexArray::new(gl);
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        Ok(Text {
+            gl: gl.clone(),
+            program, texture, vbo, vao,
+            cells, solid,
+            cell_width: cell_width as f32,
+            cell_height: cell_height as f32,
+        })
+    }
+
+    // Uniforms are set on every draw, so nothing has to be restored
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<bool, crate::Error> {
+        self.program.reload_if_changed(res)
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.cell_height
+    }
+
+    // Width and height in pixels of a possibly multiline text
+    pub fn measure(&self, text: &str) -> (f32, f32) {
+        let columns = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+        (columns as f32 * self.cell_width, text.lines().count() as f32 * self.cell_height)
+    }
+
+    // Draws text with its top left corner at (x, y) pixels over everything, optionally on a filled box
+    pub fn render(&self, viewport: &Viewport, text: &str, x: f32, y: f32,
+                  color: (f32, f32, f32, f32), background: Option<(f32, f32, f32, f32)>) {
+        let mut vertices: Vec<Vertex> = Vec::with_capacity((text.len() + 1) * 6);
+
+        if let Some(background) = background {
+            let (width, height) = self.measure(text);
+            push_quad(&mut vertices,
+                      (x - BACKGROUND_PADDING, y - BACKGROUND_PADDING,
+                       x + width + BACKGROUND_PADDING, y + height + BACKGROUND_PADDING),
+                      self.solid, background);
+        }
+
+        for (row, line) in text.lines().enumerate() {
+            let top = y + row as f32 * self.cell_height;
+            for (column, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue ;
+                }
+                let left = x + column as f32 * self.cell_width;
+                let cell = self.cells.get(&c).or_else(|| self.cells.get(&'?')).copied().unwrap();
+                push_quad(&mut vertices, (left, top, left + self.cell_width, top + self.cell_height), cell, color);
+            }
+        }
+
+        self.draw(viewport, &vertices);
+    }
+
+    // Filled rectangle (left, top, right, bottom) in pixels over everything
+    pub fn render_rect(&self, viewport: &Viewport, rect: (f32, f32, f32, f32), color: (f32, f32, f32, f32)) {
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(6);
+        push_quad(&mut vertices, rect, self.solid, color);
+        self.draw(viewport, &vertices);
+    }
+
+    fn draw(&self, viewport: &Viewport, vertices: &[Vertex]) {
+        // Several labels are drawn every frame, each one gets fresh storage
+        self.vbo.bind();
+        self.vbo.stream_draw_data(vertices);
+        self.vbo.unbind();
+
+        self.program.use_it();
+        self.program.set_uniform("viewport_size", &(viewport.w as f32, viewport.h as f32));
+        self.program.set_uniform("glyphs", &0);
+        self.texture.bind(0);
+        unsafe {
+            self.gl.Disable(gl::DEPTH_TEST);
+            self.vao.bind();
+            self.gl.DrawArrays(gl::TRIANGLES, 0, vertices.len() as gl::types::GLsizei);
+            self.vao.unbind();
+            self.gl.Enable(gl::DEPTH_TEST);
+        }
+        self.texture.unbind(0);
+    }
+}
+
+// rect is (left, top, right, bottom) in pixels
+fn push_quad(vertices: &mut Vec<Vertex>, rect: (f32, f32, f32, f32), cell: Cell, color: (f32, f32, f32, f32)) {
+    let (left, top, right, bottom) = rect;
+    let (u0, v0, u1, v1) = cell;
+    let corner = |x: f32, y: f32, u: f32, v: f32| Vertex { pos_uv: (x, y, u, v).into(), color: color.into() };
+    vertices.push(corner(left, top, u0, v0));
+    vertices.push(corner(right, top, u1, v0));
+    vertices.push(corner(right, bottom, u1, v1));
+    vertices.push(corner(left, top, u0, v0));
+    vertices.push(corner(right, bottom, u1, v1));
+    vertices.push(corner(left, bottom, u0, v1));
+}
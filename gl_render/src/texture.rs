@@ -0,0 +1,204 @@
+use std::cell::Cell;
+use std::convert::TryFrom;
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Texture data of {} bytes does not match {}x{} {:?}", size, width, height, format)]
+    DataSizeMismatch { size: usize, width: i32, height: i32, format: TextureFormat },
+}
+
+// Internal format of a texture or renderbuffer together with the client side pixel layout
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum TextureFormat {
+    R8,
+    R32F,
+    RG32F,
+    RGBA8,
+    RGBA32F,
+    Depth24,
+}
+
+impl TextureFormat {
+    pub fn internal_format(self) -> GLenum {
+        match self {
+            TextureFormat::R8 => gl::R8,
+            TextureFormat::R32F => gl::R32F,
+            TextureFormat::RG32F => gl::RG32F,
+            TextureFormat::RGBA8 => gl::RGBA8,
+            TextureFormat::RGBA32F => gl::RGBA32F,
+            TextureFormat::Depth24 => gl::DEPTH_COMPONENT24,
+        }
+    }
+
+    pub fn pixel_format(self) -> GLenum {
+        match self {
+            TextureFormat::R8 | TextureFormat::R32F => gl::RED,
+            TextureFormat::RG32F => gl::RG,
+            TextureFormat::RGBA8 | TextureFormat::RGBA32F => gl::RGBA,
+            TextureFormat::Depth24 => gl::DEPTH_COMPONENT,
+        }
+    }
+
+    pub fn pixel_type(self) -> GLenum {
+        match self {
+            TextureFormat::R8 | TextureFormat::RGBA8 => gl::UNSIGNED_BYTE,
+            TextureFormat::R32F | TextureFormat::RG32F | TextureFormat::RGBA32F | TextureFormat::Depth24 => gl::FLOAT,
+        }
+    }
+
+    // Size of one pixel in client memory
+    pub fn pixel_size(self) -> usize {
+        match self {
+            TextureFormat::R8 => 1,
+            TextureFormat::R32F | TextureFormat::RGBA8 | TextureFormat::Depth24 => 4,
+            TextureFormat::RG32F => 8,
+            TextureFormat::RGBA32F => 16,
+        }
+    }
+
+    // Bytes of a width x height image, none for negative sizes or when it does not fit in usize
+    pub fn data_size(self, width: i32, height: i32) -> Option<usize> {
+        let width = usize::try_from(width).ok()?;
+        let height = usize::try_from(height).ok()?;
+        width.checked_mul(height)?.checked_mul(self.pixel_size())
+    }
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum TextureFilter {
+    Nearest,
+    Linear,
+}
+
+impl TextureFilter {
+    fn gl_filter(self) -> GLint {
+        match self {
+            TextureFilter::Nearest => gl::NEAREST as GLint,
+            TextureFilter::Linear => gl::LINEAR as GLint,
+        }
+    }
+}
+
+// 2d texture clamped to edge, without mipmaps. Size follows the last upload
+pub struct Texture2D {
+    gl: gl::Gl,
+    id: GLuint,
+    format: TextureFormat,
+    size: Cell<(i32, i32)>,
+}
+
+impl Texture2D {
+    pub fn new(gl: &gl::Gl, format: TextureFormat, filter: TextureFilter) -> Texture2D {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl.GenTextures(1, &mut id);
+            gl.BindTexture(gl::TEXTURE_2D, id);
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter.gl_filter());
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter.gl_filter());
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+        Texture2D { gl: gl.clone(), id, format, size: Cell::new((0, 0)) }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        self.size.get()
+    }
+
+    // Rows go from the bottom of the texture (v = 0) up, data has to cover every pixel
+    pub fn upload<T>(&self, width: i32, height: i32, data: &[T]) -> Result<(), Error> {
+        let size = std::mem::size_of_val(data);
+        if self.format.data_size(width, height) != Some(size) {
+            return Err(Error::DataSizeMismatch { size, width, height, format: self.format });
+        }
+        self.image(width, height, data.as_ptr() as *const gl::types::GLvoid);
+        Ok(())
+    }
+
+    // Storage with undefined contents, for framebuffer attachments
+    pub fn allocate(&self, width: i32, height: i32) {
+        self.image(width, height, std::ptr::null());
+    }
+
+    fn image(&self, width: i32, height: i32, data: *const gl::types::GLvoid) {
+        self.size.set((width, height));
+        unsafe {
+            self.gl.BindTexture(gl::TEXTURE_2D, self.id);
+            self.gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            self.gl.TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                self.format.internal_format() as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                0,
+                self.format.pixel_format(),
+                self.format.pixel_type(),
+                data,
+            );
+            self.gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    // Pixels in the upload layout, bottom row first
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let (width, height) = self.size();
+        let mut pixels: Vec<u8> = vec![0; width as usize * height as usize * self.format.pixel_size()];
+        unsafe {
+            self.gl.BindTexture(gl::TEXTURE_2D, self.id);
+            self.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+            self.gl.GetTexImage(gl::TEXTURE_2D, 0, self.format.pixel_format(), self.format.pixel_type(),
+                                pixels.as_mut_ptr() as *mut gl::types::GLvoid);
+            self.gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+        pixels
+    }
+
+    pub fn bind(&self, unit: GLuint) {
+        unsafe {
+            self.gl.ActiveTexture(gl::TEXTURE0 + unit);
+            self.gl.BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+
+    pub fn unbind(&self, unit: GLuint) {
+        unsafe {
+            self.gl.ActiveTexture(gl::TEXTURE0 + unit);
+            self.gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+}
+
+impl Drop for Texture2D {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_size_rejects_negative_and_overflowing_sizes() {
+        assert_eq!(TextureFormat::RG32F.data_size(3, 2), Some(48));
+        assert_eq!(TextureFormat::R8.data_size(-1, -1), None);
+        assert_eq!(TextureFormat::RGBA32F.data_size(i32::MAX, i32::MAX), None);
+    }
+}
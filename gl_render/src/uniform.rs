@@ -1,3 +1,152 @@
+use gl::types::GLint;
+use crate::Program;
+
 pub trait HasUniform<T> {
     fn apply_uniform(&self, gl: &gl::Gl, data: &T, name: &str) -> Result<(), failure::Error>;
-}
\ No newline at end of file
+}
+
+// Value which can be written to a uniform location of the program in use
+pub trait Uniform {
+    fn set(&self, gl: &gl::Gl, location: GLint);
+}
+
+// Group of uniforms named `{prefix}_{field}`, or just `{field}` with empty prefix,
+// usually implemented with #[derive(Uniforms)]
+pub trait Uniforms {
+    fn set_uniforms(&self, program: &Program, prefix: &str);
+}
+
+impl Uniform for f32 {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform1f(location, *self); }
+    }
+}
+
+impl Uniform for i32 {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform1i(location, *self); }
+    }
+}
+
+impl Uniform for u32 {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform1ui(location, *self); }
+    }
+}
+
+// Also fits int uniforms used as flags
+impl Uniform for bool {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform1i(location, *self as GLint); }
+    }
+}
+
+impl Uniform for (f32, f32) {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform2f(location, self.0, self.1); }
+    }
+}
+
+impl Uniform for (f32, f32, f32) {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform3f(location, self.0, self.1, self.2); }
+    }
+}
+
+impl Uniform for (f32, f32, f32, f32) {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform4f(location, self.0, self.1, self.2, self.3); }
+    }
+}
+
+impl Uniform for (i32, i32) {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform2i(location, self.0, self.1); }
+    }
+}
+
+impl Uniform for na::Vector2<f32> {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform2f(location, self.x, self.y); }
+    }
+}
+
+impl Uniform for na::Vector3<f32> {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform3f(location, self.x, self.y, self.z); }
+    }
+}
+
+impl Uniform for na::Vector4<f32> {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform4f(location, self.x, self.y, self.z, self.w); }
+    }
+}
+
+impl Uniform for na::Point3<f32> {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform3f(location, self.x, self.y, self.z); }
+    }
+}
+
+impl Uniform for na::Matrix3<f32> {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.UniformMatrix3fv(location, 1, gl::FALSE, self.as_ptr()); }
+    }
+}
+
+impl Uniform for na::Matrix4<f32> {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.UniformMatrix4fv(location, 1, gl::FALSE, self.as_ptr()); }
+    }
+}
+
+// Arrays are written from their first element, the rest of a longer GLSL array is left as it was
+
+impl Uniform for [f32] {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform1fv(location, self.len() as GLint, self.as_ptr()); }
+    }
+}
+
+impl Uniform for [i32] {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform1iv(location, self.len() as GLint, self.as_ptr()); }
+    }
+}
+
+impl Uniform for [na::Vector2<f32>] {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform2fv(location, self.len() as GLint, self.as_ptr() as *const f32); }
+    }
+}
+
+impl Uniform for [na::Vector3<f32>] {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform3fv(location, self.len() as GLint, self.as_ptr() as *const f32); }
+    }
+}
+
+impl Uniform for [na::Vector4<f32>] {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.Uniform4fv(location, self.len() as GLint, self.as_ptr() as *const f32); }
+    }
+}
+
+impl Uniform for [na::Matrix4<f32>] {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        unsafe { gl.UniformMatrix4fv(location, self.len() as GLint, gl::FALSE, self.as_ptr() as *const f32); }
+    }
+}
+
+impl<T> Uniform for Vec<T> where [T]: Uniform {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        self.as_slice().set(gl, location);
+    }
+}
+
+impl<T, const N: usize> Uniform for [T; N] where [T]: Uniform {
+    fn set(&self, gl: &gl::Gl, location: GLint) {
+        self[..].set(gl, location);
+    }
+}
@@ -0,0 +1,183 @@
+use gl::types::GLuint;
+use crate::buffer::UniformBuffer;
+
+// Value laid out by std140 rules, structs implement it with #[derive(Std140)]
+pub trait Std140 {
+    const ALIGN: usize;     // base alignment in bytes
+
+    fn write_std140(&self, writer: &mut Std140Writer);
+}
+
+// Bytes of a uniform block, members are placed at their std140 offsets
+pub struct Std140Writer {
+    data: Vec<u8>,
+}
+
+impl Std140Writer {
+    pub fn new() -> Std140Writer {
+        Std140Writer { data: Vec::new() }
+    }
+
+    pub fn write<T: Std140>(&mut self, value: &T) {
+        self.align(T::ALIGN);
+        value.write_std140(self);
+    }
+
+    pub fn align(&mut self, align: usize) {
+        let offset = self.data.len().next_multiple_of(align);
+        self.data.resize(offset, 0);
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Default for Std140Writer {
+    fn default() -> Self {
+        Std140Writer::new()
+    }
+}
+
+// Array elements, matrix columns and structs are aligned as vec4
+const VEC4_ALIGN: usize = 16;
+
+const fn round_to_vec4(align: usize) -> usize {
+    align.next_multiple_of(VEC4_ALIGN)
+}
+
+impl Std140 for f32 {
+    const ALIGN: usize = 4;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.push(&self.to_ne_bytes());
+    }
+}
+
+impl Std140 for i32 {
+    const ALIGN: usize = 4;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.push(&self.to_ne_bytes());
+    }
+}
+
+impl Std140 for u32 {
+    const ALIGN: usize = 4;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.push(&self.to_ne_bytes());
+    }
+}
+
+// GLSL bool takes 4 bytes in a block
+impl Std140 for bool {
+    const ALIGN: usize = 4;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.push(&(*self as u32).to_ne_bytes());
+    }
+}
+
+impl Std140 for na::Vector2<f32> {
+    const ALIGN: usize = 8;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        self.iter().for_each(|value| value.write_std140(writer));
+    }
+}
+
+impl Std140 for na::Vector3<f32> {
+    const ALIGN: usize = 16;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        self.iter().for_each(|value| value.write_std140(writer));
+    }
+}
+
+impl Std140 for na::Vector4<f32> {
+    const ALIGN: usize = 16;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        self.iter().for_each(|value| value.write_std140(writer));
+    }
+}
+
+impl Std140 for na::Point3<f32> {
+    const ALIGN: usize = 16;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        self.coords.write_std140(writer);
+    }
+}
+
+// Matrices are arrays of column vectors
+impl Std140 for na::Matrix3<f32> {
+    const ALIGN: usize = VEC4_ALIGN;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        for column in self.column_iter() {
+            writer.write(&column.into_owned());
+        }
+        writer.align(VEC4_ALIGN);
+    }
+}
+
+impl Std140 for na::Matrix4<f32> {
+    const ALIGN: usize = VEC4_ALIGN;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        for column in self.column_iter() {
+            writer.write(&column.into_owned());
+        }
+    }
+}
+
+// Every element takes at least a vec4
+impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const ALIGN: usize = round_to_vec4(T::ALIGN);
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        for element in self.iter() {
+            element.write_std140(writer);
+            writer.align(Self::ALIGN);
+        }
+    }
+}
+
+// Uniform buffer attached to a binding point, programs get their blocks
+// attached to the same point with `Program::bind_uniform_block`
+pub struct UniformBlock<T: Std140> {
+    buffer: UniformBuffer,
+    binding: GLuint,
+    _marker: ::std::marker::PhantomData<T>,
+}
+
+impl<T: Std140> UniformBlock<T> {
+    pub fn new(gl: &gl::Gl, binding: GLuint) -> UniformBlock<T> {
+        UniformBlock {
+            buffer: UniformBuffer::new(gl),
+            binding,
+            _marker: ::std::marker::PhantomData,
+        }
+    }
+
+    pub fn binding(&self) -> GLuint {
+        self.binding
+    }
+
+    // Seen by every program using the block from the next draw call on
+    pub fn update(&self, value: &T) {
+        let mut writer = Std140Writer::new();
+        writer.write(value);
+        writer.align(VEC4_ALIGN);
+        self.buffer.bind();
+        self.buffer.dynamic_draw_data(writer.as_bytes());
+        self.buffer.unbind();
+        self.buffer.bind_base(self.binding);
+    }
+}
@@ -1,26 +1,31 @@
 #![recursion_limit="128"]
 
+use std::fmt::Display;
+use std::str::FromStr;
 use proc_macro::TokenStream;
-use quote::{quote};
+use quote::{quote, quote_spanned};
 use syn;
+use syn::spanned::Spanned;
 use syn::__private::TokenStream2;
 
-#[proc_macro_derive(VertexAttribPointers, attributes(location))]
+// Field types implement gl_render::data::VertexAttrib, #[divisor = N] makes the attribute per instance
+#[proc_macro_derive(VertexAttribPointers, attributes(location, divisor))]
 pub fn vertex_attrib_pointers_derive(input: TokenStream) -> TokenStream {
-    let ast = syn::parse(input).unwrap();
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
     impl_vertex_attrib_pointer(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
 }
 
-fn impl_vertex_attrib_pointer(ast: &syn::DeriveInput) -> TokenStream {
+fn impl_vertex_attrib_pointer(ast: &syn::DeriveInput) -> syn::Result<TokenStream2> {
     let ident = &ast.ident;
-    let generics = &ast.generics;
-    let where_clause = &ast.generics.where_clause;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
-    let fields_vertex_attrib_pointer
-        = generate_vertex_attrib_pointer_calls(&ast.data);
+    let fields_vertex_attrib_pointer = map_fields(struct_fields(ast, "VertexAttribPointers")?,
+                                                  |_, field| generate_struct_field_vertex_attrib_pointer_call(field))?;
 
     let gen =  quote!{
-        impl #ident #generics #where_clause {
+        impl #impl_generics #ident #ty_generics #where_clause {
             pub fn vertex_attrib_pointers(gl: &::gl::Gl) {
                 let stride = ::std::mem::size_of::<Self>();
                 let offset = 0;
@@ -28,59 +33,152 @@ fn impl_vertex_attrib_pointer(ast: &syn::DeriveInput) -> TokenStream {
             }
         }
     };
-    // panic!("code = {:#?}",  gen.to_string());
-    gen.into()
+    Ok(gen)
 }
 
-fn generate_vertex_attrib_pointer_calls(data: &syn::Data) -> Vec<TokenStream2> {
-    match data {
-        &syn::Data::Enum(_)
-        => panic!("VertexAttribPointers can not be implemented for enums"),
-        &syn::Data::Struct(ref s) => {
-            s.fields.iter()
-                .map(generate_struct_field_vertex_attrib_pointer_call)
-                .collect()
-        },
-        _ => panic!("VertexAttribPointers can not be implemented for this type"),
-    }
-}
-
-fn generate_struct_field_vertex_attrib_pointer_call(field: &syn::Field) -> TokenStream2 {
-    let field_name = match field.ident {
-        Some(ref i) => format!("{}", i),
-        None => String::from(""),
-    };
-    let location_attr = field.attrs
-        .iter()
-        .filter(|a|
-            a.path.get_ident().unwrap() == "location" && !a.tokens.is_empty())
-        .next()
-        .unwrap_or_else(|| panic!(
-            "Field {:?} is missing #[location = ?] attribute", field_name
-        ));
-
-    let attr_meta: syn::Meta = location_attr.parse_meta()
-        .unwrap_or_else(|e| panic!("{:?}", e));
-    let meta_name = match attr_meta {
-        syn::Meta::NameValue(meta_name) => meta_name,
-        _ => panic!("Field {:?} does not match #[location = ?] pattern", field_name),
-    };
-    let location_value = match meta_name.lit {
-        syn::Lit::Int(val) => val.base10_parse::<usize>().unwrap_or_else(
-            |e| panic!("Unable to parse 'location' param in field {:?} (usize param needed). Message: {:?}", field_name, e)
-        ),
-        _ => panic!("In field {:?} 'location' attribute has not int parameter", field_name),
-    };
+fn generate_struct_field_vertex_attrib_pointer_call(field: &syn::Field) -> syn::Result<TokenStream2> {
+    let location_value: usize = int_attribute(field, "location")?
+        .ok_or_else(|| syn::Error::new_spanned(field, "field is missing #[location = N] attribute"))?;
+    let divisor = int_attribute::<u32>(field, "divisor")?.map(|divisor| quote! {
+        gl.VertexAttribDivisor(location as ::gl::types::GLuint, #divisor);
+    });
 
     let field_ty = &field.ty;
+    // Types without vertex layout are reported at the field type
+    let attrib_pointer = quote_spanned! {field_ty.span()=>
+        <#field_ty as ::gl_render::data::VertexAttrib>::vertex_attrib_pointer(gl, stride, location, offset);
+    };
 
     let gen = quote! {
         let location = #location_value;
         unsafe {
-            #field_ty::vertex_attrib_pointer(gl, stride, location, offset);
+            #attrib_pointer
+            #divisor
         }
         let offset = offset + ::std::mem::size_of::<#field_ty>();
     };
 
-    gen
-}
\ No newline at end of file
+    Ok(gen)
+}
+
+#[proc_macro_derive(Uniforms, attributes(uniform))]
+pub fn uniforms_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
+    impl_uniforms(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn impl_uniforms(ast: &syn::DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let fields_set_uniform = map_fields(struct_fields(ast, "Uniforms")?,
+                                        |_, field| generate_struct_field_set_uniform_call(field))?;
+
+    let gen = quote!{
+        impl #impl_generics ::gl_render::uniform::Uniforms for #ident #ty_generics #where_clause {
+            fn set_uniforms(&self, program: &::gl_render::Program, prefix: &str) {
+                #(#fields_set_uniform)*
+            }
+        }
+    };
+    Ok(gen)
+}
+
+// Uniform is named after the field unless renamed with #[uniform = "name"]
+fn generate_struct_field_set_uniform_call(field: &syn::Field) -> syn::Result<TokenStream2> {
+    let field_ident = field.ident.as_ref()
+        .ok_or_else(|| syn::Error::new_spanned(field, "Uniforms can only be implemented for structs with named fields"))?;
+    let field_name = format!("{}", field_ident);
+
+    let uniform_name = match field.attrs.iter().find(|a| a.path.is_ident("uniform")) {
+        None => field_name,
+        Some(attr) => match attr.parse_meta()? {
+            syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(name), .. }) => name.value(),
+            meta => return Err(syn::Error::new_spanned(meta, "expected #[uniform = \"name\"]")),
+        },
+    };
+
+    Ok(quote! {
+        program.set_prefixed_uniform(prefix, #uniform_name, &self.#field_ident);
+    })
+}
+
+
+#[proc_macro_derive(Std140)]
+pub fn std140_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
+    impl_std140(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+// Struct members follow each other in declaration order, the struct itself is aligned as vec4
+fn impl_std140(ast: &syn::DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let fields_write = map_fields(struct_fields(ast, "Std140")?, |index, field| Ok(match field.ident {
+        Some(ref field_ident) => quote! { writer.write(&self.#field_ident); },
+        None => {
+            let index = syn::Index::from(index);
+            quote! { writer.write(&self.#index); }
+        },
+    }))?;
+
+    let gen = quote!{
+        impl #impl_generics ::gl_render::uniform_block::Std140 for #ident #ty_generics #where_clause {
+            const ALIGN: usize = 16;
+
+            fn write_std140(&self, writer: &mut ::gl_render::uniform_block::Std140Writer) {
+                #(#fields_write)*
+                writer.align(16);
+            }
+        }
+    };
+    Ok(gen)
+}
+
+fn struct_fields<'a>(ast: &'a syn::DeriveInput, derive: &str) -> syn::Result<&'a syn::Fields> {
+    match &ast.data {
+        syn::Data::Struct(s) => Ok(&s.fields),
+        syn::Data::Enum(e) => Err(syn::Error::new_spanned(
+            e.enum_token, format!("{} can not be implemented for enums", derive))),
+        syn::Data::Union(u) => Err(syn::Error::new_spanned(
+            u.union_token, format!("{} can not be implemented for unions", derive))),
+    }
+}
+
+// Every field is looked at, so all wrong ones are reported at once
+fn map_fields<F>(fields: &syn::Fields, generate: F) -> syn::Result<Vec<TokenStream2>>
+    where F: Fn(usize, &syn::Field) -> syn::Result<TokenStream2>
+{
+    let mut generated = Vec::new();
+    let mut error: Option<syn::Error> = None;
+    for (index, field) in fields.iter().enumerate() {
+        match (generate(index, field), &mut error) {
+            (Ok(gen), _) => generated.push(gen),
+            (Err(e), Some(error)) => error.combine(e),
+            (Err(e), None) => error = Some(e),
+        }
+    }
+    match error {
+        Some(error) => Err(error),
+        None => Ok(generated),
+    }
+}
+
+// Value of #[name = N] on the field, None without the attribute
+fn int_attribute<N>(field: &syn::Field, name: &str) -> syn::Result<Option<N>>
+    where N: FromStr, N::Err: Display
+{
+    let attr = match field.attrs.iter().find(|a| a.path.is_ident(name)) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    match attr.parse_meta()? {
+        syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Int(value), .. }) => value.base10_parse().map(Some),
+        meta => Err(syn::Error::new_spanned(meta, format!("expected #[{} = N]", name))),
+    }
+}
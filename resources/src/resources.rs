@@ -1,7 +1,8 @@
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use std::ffi;
+use std::time::SystemTime;
 
 #[derive(Debug, Fail)]
 pub enum Error {
@@ -19,6 +20,7 @@ impl From<io::Error> for Error {
     }
 }
 
+#[derive(Clone)]
 pub struct Resources {
     root_path: PathBuf,
 }
@@ -34,6 +36,10 @@ impl Resources {
         })
     }
 
+    pub fn from_path(root_path: &Path) -> Resources {
+        Resources { root_path: root_path.into() }
+    }
+
     pub fn load_cstring(&self, resource_name: &str) -> Result<ffi::CString, Error> {
         let mut file = fs::File::open(
             resource_name_to_path(&self.root_path, resource_name)
@@ -49,6 +55,33 @@ impl Resources {
 
         Ok(unsafe { ffi::CString::from_vec_unchecked(buffer) })
     }
+
+    pub fn path(&self, resource_name: &str) -> PathBuf {
+        resource_name_to_path(&self.root_path, resource_name)
+    }
+
+    pub fn exists(&self, resource_name: &str) -> bool {
+        resource_name_to_path(&self.root_path, resource_name).is_file()
+    }
+
+    // Last modification time, None if the resource is missing
+    pub fn modified(&self, resource_name: &str) -> Option<SystemTime> {
+        fs::metadata(resource_name_to_path(&self.root_path, resource_name))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    pub fn load_bytes(&self, resource_name: &str) -> Result<Vec<u8>, Error> {
+        Ok(fs::read(resource_name_to_path(&self.root_path, resource_name))?)
+    }
+
+    pub fn save_string(&self, resource_name: &str, data: &str) -> Result<(), Error> {
+        let mut file = fs::File::create(
+            resource_name_to_path(&self.root_path, resource_name)
+        )?;
+        file.write_all(data.as_bytes())?;
+        Ok(())
+    }
 }
 
 fn resource_name_to_path(root_dir: &Path, location: &str) -> PathBuf {
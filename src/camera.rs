@@ -1,52 +1,205 @@
 use na;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_4, PI};
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+}
+
+const FOV_Y: f32 = FRAC_PI_4;
+const PERSPECTIVE_NEAR: f32 = 0.01;
+const PERSPECTIVE_FAR: f32 = 100.;
+const ORTHOGRAPHIC_DEPTH: f32 = 30.;
+const DISTANCE_MIN: f32 = 0.1;
+const DISTANCE_MAX: f32 = 20.;
+const ZOOM_FACTOR: f32 = 0.9;
+const PITCH_LIMIT: f32 = FRAC_PI_2 - 0.01;
+
+// Orbit camera placement: looks at `target` from `distance` away, rotated by `yaw` around y and `pitch` around x
+#[derive(Copy, Clone, Debug)]
+pub struct CameraPose {
+    pub target: na::Point3<f32>,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl CameraPose {
+    pub fn default_view() -> CameraPose {
+        CameraPose {
+            target: na::Point3::new(0., 0.2, 0.),
+            yaw: FRAC_PI_3,
+            pitch: FRAC_PI_3,
+            distance: 3.5,
+        }
+    }
+
+    // Catmull-Rom spline through p1 (t = 0) and p2 (t = 1)
+    pub fn catmull_rom(p0: &CameraPose, p1: &CameraPose, p2: &CameraPose, p3: &CameraPose, t: f32) -> CameraPose {
+        let spline = |v0: f32, v1: f32, v2: f32, v3: f32| {
+            0.5 * (2. * v1
+                + (v2 - v0) * t
+                + (2. * v0 - 5. * v1 + 4. * v2 - v3) * t * t
+                + (3. * v1 - v0 - 3. * v2 + v3) * t * t * t)
+        };
+        CameraPose {
+            target: na::Point3::new(
+                spline(p0.target.x, p1.target.x, p2.target.x, p3.target.x),
+                spline(p0.target.y, p1.target.y, p2.target.y, p3.target.y),
+                spline(p0.target.z, p1.target.z, p2.target.z, p3.target.z)),
+            yaw: spline(p0.yaw, p1.yaw, p2.yaw, p3.yaw),
+            pitch: spline(p0.pitch, p1.pitch, p2.pitch, p3.pitch).clamp(-PITCH_LIMIT, PITCH_LIMIT),
+            distance: spline(p0.distance, p1.distance, p2.distance, p3.distance).clamp(DISTANCE_MIN, DISTANCE_MAX),
+        }
+    }
+}
+
+// Camera uniform block
+#[derive(Std140)]
+pub struct CameraBlock {
+    mvp_transform: na::Matrix4<f32>,
+    eye_position: na::Point3<f32>,
+}
 
 #[derive(Copy, Clone, Debug)]
-#[repr(C, packed)]
 pub struct MVP {
     model: na::Matrix4<f32>,
-    view_rotation: na::Matrix4<f32>,
-    view_translation: na::Matrix4<f32>,
+    pose: CameraPose,
+    projection_mode: Projection,
+    aspect: f32,
+    view: na::Matrix4<f32>,
     projection: na::Matrix4<f32>,
 }
 
 impl MVP {
     pub fn new() -> MVP {
+        let mut mvp = MVP {
+            model: na::Matrix4::identity(),
+            pose: CameraPose::default_view(),
+            projection_mode: Projection::Perspective,
+            aspect: 900. / 700.,    // TODO: add size to config
+            view: na::Matrix4::identity(),
+            projection: na::Matrix4::identity(),
+        };
+        mvp.reset();
+        mvp
+    }
 
-        let model = na::Isometry3::identity();
+    pub fn reset(&mut self) {
+        self.set_pose(&CameraPose::default_view());
+    }
 
-        let mut model: na::Matrix4<f32> = model.to_homogeneous() * 1.;
-        model[15] = 1.;
+    pub fn get_pose(&self) -> CameraPose {
+        self.pose
+    }
 
-        let view_rotation: na::Matrix4<f32> = na::Isometry3::rotation(na::Vector3::y() * 3.14 / 3.).to_homogeneous();
-        let view_rotation = na::Isometry3::rotation(na::Vector3::x() * 3.14 / 3.).to_homogeneous() * view_rotation;
-        let view_translation: na::Matrix4<f32> = na::Isometry3::translation(0., -1., -2.).to_homogeneous();
+    pub fn set_pose(&mut self, pose: &CameraPose) {
+        self.pose = *pose;
+        self.view_recalc();
+        self.projection_update();
+    }
 
-        let projection: na::Matrix4<f32> = na::Orthographic3::new(-1.41, 1.41, -2.5, 1., -30., 30.)
-            .to_homogeneous();
+    pub fn get_transform(&self) -> na::Matrix4<f32> {
+        self.projection * self.view * self.model
+    }
 
-        MVP {
-            model,
-            view_rotation,
-            view_translation,
-            projection,
-        }
+    pub fn get_eye(&self) -> na::Point3<f32> {
+        self.pose.target + self.rotation().inverse() * na::Vector3::z() * self.pose.distance
     }
 
-    pub fn get_transform(&self) -> na::Matrix4<f32> {
-        self.projection * self.view_translation * self.view_rotation * self.model
+    pub fn block(&self) -> CameraBlock {
+        CameraBlock { mvp_transform: self.get_transform(), eye_position: self.get_eye() }
+    }
+
+    // Returns ray from near to far plane as (near point, far point - near point)
+    pub fn cursor_ray(&self, ndc: na::Vector2<f32>) -> Option<(na::Point3<f32>, na::Vector3<f32>)> {
+        let inverse = self.get_transform().try_inverse()?;
+        let near = inverse.transform_point(&na::Point3::new(ndc.x, ndc.y, -1.));
+        let far = inverse.transform_point(&na::Point3::new(ndc.x, ndc.y, 1.));
+        Some((near, far - near))
+    }
+
+    pub fn get_projection_mode(&self) -> Projection {
+        self.projection_mode
+    }
+
+    pub fn toggle_projection(&mut self) {
+        self.projection_mode = match self.projection_mode {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        };
+        self.projection_update();
     }
 
     pub fn projection_recalc(&mut self, w: i32, h: i32) {
-        let aspect: f32 = (w) as f32 / (h) as f32;
-        println!("aspect: {}", aspect);
-        self.projection = na::Orthographic3::new(-1.41  * aspect, 1.41 * aspect, -2.5, 1., -30., 30.)
-            .to_homogeneous();
+        self.aspect = (w) as f32 / (h) as f32;
+        self.projection_update();
     }
 
+    // Orbits around target, naviball is cursor shift relative to viewport size
     pub fn view_rotate_naviball(&mut self, naviball: na::Vector2<f32>) {
-        let rot_y = na::Isometry3::rotation(na::Vector3::y() * 3.14 * naviball.x);
-        let rot_x = na::Isometry3::rotation(na::Vector3::x() * 3.14 * naviball.y);
-        let rot_total: na::Matrix4<f32> = (rot_x * rot_y).to_homogeneous();
-        self.view_rotation = rot_total * self.view_rotation;
+        self.pose.yaw = (self.pose.yaw + PI * naviball.x) % (2. * PI);
+        self.pose.pitch = (self.pose.pitch + PI * naviball.y).clamp(-PITCH_LIMIT, PITCH_LIMIT);
+        self.view_recalc();
+    }
+
+    // Moves target in view plane, shift is cursor shift relative to viewport height
+    pub fn pan(&mut self, shift: na::Vector2<f32>) {
+        let scale = 2. * self.visible_half_height();
+        let (right, up, _) = self.view_axes();
+        self.pose.target -= right * shift.x * scale * self.aspect;
+        self.pose.target += up * shift.y * scale;
+        self.view_recalc();
+    }
+
+    // Positive steps move camera closer to target
+    pub fn zoom(&mut self, steps: i32) {
+        self.pose.distance = (self.pose.distance * ZOOM_FACTOR.powi(steps)).clamp(DISTANCE_MIN, DISTANCE_MAX);
+        self.view_recalc();
+        self.projection_update();
+    }
+
+    // Moves target (with camera) along view axes: (right, up, forward) scaled by distance to target
+    pub fn fly(&mut self, direction: na::Vector3<f32>) {
+        let (right, up, forward) = self.view_axes();
+        self.pose.target += (right * direction.x + up * direction.y + forward * direction.z) * self.pose.distance;
+        self.view_recalc();
+    }
+
+    fn view_axes(&self) -> (na::Vector3<f32>, na::Vector3<f32>, na::Vector3<f32>) {
+        let rotation = self.rotation().inverse();
+        (rotation * na::Vector3::x(), rotation * na::Vector3::y(), rotation * -na::Vector3::z())
+    }
+
+    fn rotation(&self) -> na::UnitQuaternion<f32> {
+        na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), self.pose.pitch)
+            * na::UnitQuaternion::from_axis_angle(&na::Vector3::y_axis(), self.pose.yaw)
+    }
+
+    fn visible_half_height(&self) -> f32 {
+        self.pose.distance * (FOV_Y / 2.).tan()
+    }
+
+    fn view_recalc(&mut self) {
+        self.view = na::Translation3::new(0., 0., -self.pose.distance).to_homogeneous()
+            * self.rotation().to_homogeneous()
+            * na::Translation3::from(-self.pose.target.coords).to_homogeneous();
+    }
+
+    fn projection_update(&mut self) {
+        self.projection = match self.projection_mode {
+            Projection::Perspective => na::Perspective3::new(
+                self.aspect, FOV_Y, PERSPECTIVE_NEAR, PERSPECTIVE_FAR
+            ).to_homogeneous(),
+            Projection::Orthographic => {
+                let half_h = self.visible_half_height();
+                let half_w = half_h * self.aspect;
+                na::Orthographic3::new(-half_w, half_w, -half_h, half_h,
+                                       -ORTHOGRAPHIC_DEPTH, ORTHOGRAPHIC_DEPTH).to_homogeneous()
+            },
+        };
     }
 }
@@ -0,0 +1,182 @@
+use resources::Resources;
+use failure::err_msg;
+use std::f32::consts::PI;
+use crate::camera::CameraPose;
+
+const BOOKMARK_FRAMES: usize = 120;       // TODO: add to config
+const TRANSITION_FRAMES: usize = 60;
+
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "Unable to convert file {} to string", name)]
+    UnableConvertFileToString { name: String },
+    #[fail(display = "Bookmark {} does not have 8 components (name, frames, x, y, z, yaw, pitch, distance)", name)]
+    BookmarkDoNotHave8Components { name: String },
+    #[fail(display = "Invalid bookmark component: {}, {}", name, message)]
+    ComponentNotValid { name: String, message: String },
+}
+
+pub struct Bookmark {
+    pub name: String,
+    pub frames: usize,      // flight time from previous bookmark during path playback
+    pub pose: CameraPose,
+}
+
+// Interpolates camera through keyframes, one frame per simulation step
+struct Playback {
+    keys: Vec<CameraPose>,
+    frames: Vec<usize>,     // frames of segment i between keys i and i + 1
+    eased: bool,
+    segment: usize,
+    frame: usize,
+}
+
+// Named camera bookmarks stored next to the grid file, playable as a keyframed path
+pub struct CameraPath {
+    path: String,
+    bookmarks: Vec<Bookmark>,
+    playback: Option<Playback>,
+}
+
+impl CameraPath {
+    // Bookmarks of grids/name.mod1 are kept in grids/name.cam, missing file means no bookmarks
+    pub fn new(res: &Resources, grid_path: &str) -> Result<CameraPath, failure::Error> {
+        let path = format!("{}.cam", grid_path.trim_end_matches(".mod1"));
+        let bookmarks = match res.load_cstring(&path) {
+            Ok(file) => {
+                let file = file.into_string()
+                    .map_err(|_| Error::UnableConvertFileToString { name: path.clone() })?;
+                parse_bookmarks(&file)?
+            },
+            Err(_) => vec![],
+        };
+        Ok(CameraPath { path, bookmarks, playback: None })
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn add_bookmark(&mut self, res: &Resources, pose: CameraPose) -> Result<&Bookmark, failure::Error> {
+        self.bookmarks.push(Bookmark {
+            name: format!("view_{}", self.bookmarks.len() + 1),
+            frames: BOOKMARK_FRAMES,
+            pose,
+        });
+        res.save_string(&self.path, &bookmarks_to_string(&self.bookmarks)).map_err(err_msg)?;
+        Ok(self.bookmarks.last().unwrap())
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    pub fn stop(&mut self) {
+        self.playback = None;
+    }
+
+    // Smooth flight from current pose to bookmark idx
+    pub fn go_to(&mut self, idx: usize, current: CameraPose) -> Option<&Bookmark> {
+        let bookmark = self.bookmarks.get(idx)?;
+        self.playback = Some(Playback::new(vec![current, bookmark.pose], vec![TRANSITION_FRAMES], true));
+        Some(bookmark)
+    }
+
+    // Flight through all bookmarks in order starting from the first one
+    pub fn play(&mut self) -> bool {
+        if self.bookmarks.len() < 2 {
+            return false;
+        }
+        let keys = self.bookmarks.iter().map(|bookmark| bookmark.pose).collect();
+        let frames = self.bookmarks.iter().skip(1).map(|bookmark| bookmark.frames.max(1)).collect();
+        self.playback = Some(Playback::new(keys, frames, false));
+        true
+    }
+
+    // Advances playback by one frame, returns pose camera should take
+    pub fn step(&mut self) -> Option<CameraPose> {
+        let playback = self.playback.as_mut()?;
+        let pose = playback.pose();
+        if !playback.advance() {
+            self.playback = None;
+        }
+        Some(pose)
+    }
+}
+
+impl Playback {
+    fn new(mut keys: Vec<CameraPose>, frames: Vec<usize>, eased: bool) -> Playback {
+        // Take the short way around between neighbour yaws
+        for i in 1..keys.len() {
+            let prev = keys[i - 1].yaw;
+            let yaw = &mut keys[i].yaw;
+            while *yaw - prev > PI { *yaw -= 2. * PI; }
+            while *yaw - prev < -PI { *yaw += 2. * PI; }
+        }
+        Playback { keys, frames, eased, segment: 0, frame: 0 }
+    }
+
+    fn pose(&self) -> CameraPose {
+        let last = self.keys.len() - 1;
+        let i = self.segment;
+        let t = self.frame as f32 / self.frames[i] as f32;
+        let t = match self.eased {
+            true => t * t * (3. - 2. * t),
+            false => t,
+        };
+        CameraPose::catmull_rom(
+            &self.keys[i.saturating_sub(1)],
+            &self.keys[i],
+            &self.keys[i + 1],
+            &self.keys[std::cmp::min(i + 2, last)],
+            t)
+    }
+
+    // Returns false when the last keyframe is reached
+    fn advance(&mut self) -> bool {
+        self.frame += 1;
+        if self.frame > self.frames[self.segment] {
+            self.frame = 1;
+            self.segment += 1;
+        }
+        self.segment < self.frames.len()
+    }
+}
+
+fn bookmarks_to_string(bookmarks: &[Bookmark]) -> String {
+    bookmarks.iter()
+        .map(|bookmark| format!("{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4}",
+                                bookmark.name, bookmark.frames,
+                                bookmark.pose.target.x, bookmark.pose.target.y, bookmark.pose.target.z,
+                                bookmark.pose.yaw, bookmark.pose.pitch, bookmark.pose.distance))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn parse_bookmarks(file: &str) -> Result<Vec<Bookmark>, Error> {
+    file.split('\n')
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let components: Vec<&str> = line.split(',').map(|component| component.trim()).collect();
+            if components.len() != 8 {
+                return Err(Error::BookmarkDoNotHave8Components { name: line.to_string() });
+            }
+            let frames = components[1].parse::<usize>()
+                .map_err(|e| Error::ComponentNotValid { name: components[1].to_string(), message: e.to_string() })?;
+            let values = components[2..].iter()
+                .map(|component| component.parse::<f32>()
+                    .map_err(|e| Error::ComponentNotValid { name: component.to_string(), message: e.to_string() }))
+                .collect::<Result<Vec<f32>, Error>>()?;
+            Ok(Bookmark {
+                name: components[0].to_string(),
+                frames,
+                pose: CameraPose {
+                    target: na::Point3::new(values[0], values[1], values[2]),
+                    yaw: values[3],
+                    pitch: values[4],
+                    distance: values[5],
+                },
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,217 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::str::FromStr;
+use chrono::prelude::*;
+use gl_render::framebuffer::{self, Framebuffer, Renderbuffer};
+use gl_render::texture::TextureFormat;
+
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "Offscreen framebuffer is unusable")]
+    Offscreen { #[cause] inner: framebuffer::Error },
+    #[fail(display = "Unable to read offscreen pixels")]
+    ReadPixels { #[cause] inner: framebuffer::Error },
+    #[fail(display = "Unable to create {}: {}", name, message)]
+    UnableCreateFile { name: String, message: String },
+    #[fail(display = "Unable to encode {}: {}", name, message)]
+    EncodingFailed { name: String, message: String },
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum RecordFormat {
+    Png,    // numbered frame sequence
+    Gif,
+}
+
+impl FromStr for RecordFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(RecordFormat::Png),
+            "gif" => Ok(RecordFormat::Gif),
+            _ => Err(format!("unknown record format {}, expected png or gif", s)),
+        }
+    }
+}
+
+// Colour and depth renderbuffers the scene is drawn into when frames have to be read back
+pub struct Offscreen {
+    framebuffer: Framebuffer,
+    color: Renderbuffer,
+    depth: Renderbuffer,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Offscreen {
+    pub fn new(gl: &gl::Gl, width: i32, height: i32) -> Result<Offscreen, Error> {
+        let offscreen = Offscreen {
+            framebuffer: Framebuffer::new(gl),
+            color: Renderbuffer::new(gl, TextureFormat::RGBA8, width, height),
+            depth: Renderbuffer::new(gl, TextureFormat::Depth24, width, height),
+            width,
+            height,
+        };
+        offscreen.framebuffer.attach_renderbuffer(gl::COLOR_ATTACHMENT0, &offscreen.color);
+        offscreen.framebuffer.attach_renderbuffer(gl::DEPTH_ATTACHMENT, &offscreen.depth);
+        offscreen.check()?;
+        Ok(offscreen)
+    }
+
+    pub fn resize(&mut self, width: i32, height: i32) -> Result<(), Error> {
+        self.width = width;
+        self.height = height;
+        self.color.resize(width, height);
+        self.depth.resize(width, height);
+        self.check()
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        self.framebuffer.check().map_err(|e| Error::Offscreen { inner: e })
+    }
+
+    pub fn bind(&self) {
+        self.framebuffer.bind();
+    }
+
+    pub fn unbind(&self) {
+        self.framebuffer.unbind();
+    }
+
+    // RGBA rows from top to bottom, alpha is forced to opaque
+    pub fn read_pixels(&self) -> Result<Vec<u8>, Error> {
+        let row = self.width as usize * 4;
+        let pixels = self.framebuffer.read_pixels(self.width, self.height, TextureFormat::RGBA8)
+            .map_err(|e| Error::ReadPixels { inner: e })?;
+        let mut flipped: Vec<u8> = pixels.chunks(row).rev().flatten().copied().collect();
+        flipped.iter_mut().skip(3).step_by(4).for_each(|alpha| *alpha = 255);
+        Ok(flipped)
+    }
+
+    // Copies the picture to the window framebuffer
+    pub fn blit_to_screen(&self) {
+        self.framebuffer.blit_to_screen(self.width, self.height);
+    }
+}
+
+enum Session {
+    Frames { dir: String },
+    Gif { path: String, encoder: gif::Encoder<BufWriter<File>> },
+}
+
+// Writes screenshots and recordings into dir, a recording takes a frame every `every` simulation steps
+pub struct Recorder {
+    dir: String,
+    format: RecordFormat,
+    every: usize,
+    gif_delay: u16,     // in hundredths of a second
+    session: Option<Session>,
+    frames: usize,
+}
+
+impl Recorder {
+    pub fn new(dir: &str, format: RecordFormat, every: usize, gif_delay: u16) -> Recorder {
+        Recorder {
+            dir: dir.to_owned(),
+            format,
+            every: every.max(1),
+            gif_delay,
+            session: None,
+            frames: 0,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.session.is_some()
+    }
+
+    pub fn get_frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn need_frame(&self, step: usize) -> bool {
+        self.is_recording() && step % self.every == 0
+    }
+
+    // Returns where frames go
+    pub fn start(&mut self, width: i32, height: i32) -> Result<String, Error> {
+        let name = format!("{}/recording_{}", self.dir, timestamp());
+        let session = match self.format {
+            RecordFormat::Png => {
+                create_dir(&name)?;
+                Session::Frames { dir: name.clone() }
+            },
+            RecordFormat::Gif => {
+                create_dir(&self.dir)?;
+                let path = format!("{}.gif", name);
+                let file = File::create(&path)
+                    .map_err(|e| Error::UnableCreateFile { name: path.clone(), message: e.to_string() })?;
+                let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[])
+                    .map_err(|e| Error::EncodingFailed { name: path.clone(), message: e.to_string() })?;
+                encoder.set_repeat(gif::Repeat::Infinite)
+                    .map_err(|e| Error::EncodingFailed { name: path.clone(), message: e.to_string() })?;
+                Session::Gif { path, encoder }
+            },
+        };
+        let destination = match &session {
+            Session::Frames { dir } => dir.clone(),
+            Session::Gif { path, .. } => path.clone(),
+        };
+        self.session = Some(session);
+        self.frames = 0;
+        Ok(destination)
+    }
+
+    // Gif is finished when its encoder is dropped
+    pub fn stop(&mut self) -> Option<usize> {
+        self.session.take().map(|_| self.frames)
+    }
+
+    pub fn add_frame(&mut self, pixels: &mut [u8], width: i32, height: i32) -> Result<(), Error> {
+        let gif_delay = self.gif_delay;
+        match self.session.as_mut() {
+            Some(Session::Frames { dir }) => {
+                write_png(&format!("{}/frame_{:05}.png", dir, self.frames), pixels, width, height)?;
+            },
+            Some(Session::Gif { path, encoder }) => {
+                let mut frame = gif::Frame::from_rgba_speed(width as u16, height as u16, pixels, 10);
+                frame.delay = gif_delay;
+                encoder.write_frame(&frame)
+                    .map_err(|e| Error::EncodingFailed { name: path.clone(), message: e.to_string() })?;
+            },
+            None => return Ok(()),
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn screenshot(&self, pixels: &[u8], width: i32, height: i32) -> Result<String, Error> {
+        create_dir(&self.dir)?;
+        let path = format!("{}/screenshot_{}.png", self.dir, timestamp());
+        write_png(&path, pixels, width, height)?;
+        Ok(path)
+    }
+}
+
+fn timestamp() -> String {
+    Local::now().format("%Y%m%d_%H%M%S_%3f").to_string()
+}
+
+fn create_dir(dir: &str) -> Result<(), Error> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| Error::UnableCreateFile { name: dir.to_owned(), message: e.to_string() })
+}
+
+fn write_png(path: &str, pixels: &[u8], width: i32, height: i32) -> Result<(), Error> {
+    let file = File::create(path)
+        .map_err(|e| Error::UnableCreateFile { name: path.to_owned(), message: e.to_string() })?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .map_err(|e| Error::EncodingFailed { name: path.to_owned(), message: e.to_string() })
+}
@@ -0,0 +1,68 @@
+use resources::Resources;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "Unable to convert file {} to string", name)]
+    UnableConvertFileToString { name: String },
+    #[fail(display = "Line {} does not match `key = value` pattern", name)]
+    LineIsNotKeyValue { name: String },
+    #[fail(display = "Invalid value of {}: {}", name, message)]
+    ValueNotValid { name: String, message: String },
+}
+
+// `key = value` settings, `#` at the start of a line or after whitespace starts a comment,
+// so values like `0:#00cc00` are kept. Missing file means all defaults
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(res: &Resources, name: &str) -> Result<Config, Error> {
+        let file = match res.load_cstring(name) {
+            Ok(file) => file.into_string().map_err(|_| Error::UnableConvertFileToString { name: name.into() })?,
+            Err(_) => String::new(),
+        };
+        Config::parse(&file)
+    }
+
+    pub fn parse(file: &str) -> Result<Config, Error> {
+        let mut values = HashMap::new();
+        for line in file.lines() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue ;
+            }
+            let mut key_value = line.splitn(2, '=');
+            match (key_value.next(), key_value.next()) {
+                (Some(key), Some(value)) => values.insert(key.trim().to_owned(), value.trim().to_owned()),
+                _ => return Err(Error::LineIsNotKeyValue { name: line.to_owned() }),
+            };
+        }
+        Ok(Config { values })
+    }
+
+    pub fn get_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, Error>
+        where T::Err: std::fmt::Display
+    {
+        match self.values.get(key) {
+            Some(value) => value.parse::<T>().map_err(|e| Error::ValueNotValid {
+                name: key.to_owned(),
+                message: e.to_string(),
+            }),
+            None => Ok(default),
+        }
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut after_space = true;
+    for (idx, c) in line.char_indices() {
+        if c == '#' && after_space {
+            return &line[..idx];
+        }
+        after_space = c.is_whitespace();
+    }
+    line
+}
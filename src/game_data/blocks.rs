@@ -0,0 +1,34 @@
+use gl_render::Program;
+use gl_render::uniform_block::UniformBlock;
+use crate::camera::CameraBlock;
+use crate::game_data::light::LightBlock;
+use crate::game_data::color_map::ColorMapBlock;
+
+// Binding points of the blocks, shaders refer to them by block name
+const CAMERA: (&str, u32) = ("Camera", 0);
+const LIGHT: (&str, u32) = ("Light", 1);
+const COLOR_MAP: (&str, u32) = ("ColorMap", 2);
+
+// Attaches the shared blocks a program declares, called once after the program is linked
+pub fn bind(program: &Program) {
+    for (name, binding) in [CAMERA, LIGHT, COLOR_MAP] {
+        program.bind_uniform_block(name, binding);
+    }
+}
+
+// Uniform buffers uploaded once per change and read by every program, the compared scene included
+pub struct Blocks {
+    pub camera: UniformBlock<CameraBlock>,
+    pub light: UniformBlock<LightBlock>,
+    pub color_map: UniformBlock<ColorMapBlock>,
+}
+
+impl Blocks {
+    pub fn new(gl: &gl::Gl) -> Blocks {
+        Blocks {
+            camera: UniformBlock::new(gl, CAMERA.1),
+            light: UniformBlock::new(gl, LIGHT.1),
+            color_map: UniformBlock::new(gl, COLOR_MAP.1),
+        }
+    }
+}
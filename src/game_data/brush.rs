@@ -0,0 +1,107 @@
+use std::ops::Range;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum BrushTool {
+    Raise,
+    Lower,
+    Smooth,
+    Flatten,
+}
+
+pub struct Brush {
+    pub tool: BrushTool,
+    pub radius: f32,            // in grid cells
+    pub strength: f32,          // max height change per application
+    flatten_height: Option<f32>,
+}
+
+// Grid cells touched by one brush application as (rows, cols)
+pub type BrushArea = (Range<usize>, Range<usize>);
+
+const BRUSH_RADIUS_MIN: f32 = 1.;
+const BRUSH_RADIUS_MAX: f32 = 50.;
+const BRUSH_RADIUS_STEP: f32 = 1.;
+
+impl Brush {
+    pub fn new() -> Brush {
+        Brush {
+            tool: BrushTool::Raise,
+            radius: 8.,         // TODO: add to config
+            strength: 0.005,
+            flatten_height: None,
+        }
+    }
+
+    pub fn increase_radius(&mut self) {
+        self.radius = (self.radius + BRUSH_RADIUS_STEP).clamp(BRUSH_RADIUS_MIN, BRUSH_RADIUS_MAX);
+    }
+
+    pub fn decrease_radius(&mut self) {
+        self.radius = (self.radius - BRUSH_RADIUS_STEP).clamp(BRUSH_RADIUS_MIN, BRUSH_RADIUS_MAX);
+    }
+
+    // Flatten keeps the height found under the cursor when the stroke started
+    pub fn end_stroke(&mut self) {
+        self.flatten_height = None;
+    }
+
+    pub fn apply(&mut self, data: &mut [Vec<f32>], row: usize, col: usize) -> BrushArea {
+        let size = data.len();
+        let r = self.radius.ceil() as usize;
+        let rows = row.saturating_sub(r)..std::cmp::min(row + r + 1, size);
+        let cols = col.saturating_sub(r)..std::cmp::min(col + r + 1, size);
+
+        let target = *self.flatten_height.get_or_insert(data[row][col]);
+        let source: Vec<Vec<f32>> = match self.tool {
+            BrushTool::Smooth => data.to_vec(),
+            _ => vec![],
+        };
+
+        for i in rows.clone() {
+            for j in cols.clone() {
+                let weight = self.falloff(i, j, row, col);
+                if weight <= 0. {
+                    continue ;
+                }
+                let height = &mut data[i][j];
+                *height = match self.tool {
+                    BrushTool::Raise => *height + self.strength * weight,
+                    BrushTool::Lower => *height - self.strength * weight,
+                    BrushTool::Smooth => lerp(*height, neighbours_avg(&source, i, j), weight * 0.5),
+                    BrushTool::Flatten => lerp(*height, target, weight * 0.5),
+                }.clamp(0., 1.);
+            }
+        }
+        (rows, cols)
+    }
+
+    fn falloff(&self, i: usize, j: usize, row: usize, col: usize) -> f32 {
+        let di = i as f32 - row as f32;
+        let dj = j as f32 - col as f32;
+        let dist = (di * di + dj * dj).sqrt() / self.radius;
+        if dist >= 1. {
+            0.
+        }
+        else {
+            (1. - dist * dist).powf(2.)
+        }
+    }
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+fn neighbours_avg(data: &[Vec<f32>], i: usize, j: usize) -> f32 {
+    let mut sum = 0.;
+    let mut count = 0;
+    for row in data.iter().skip(i.saturating_sub(1)).take(if i > 0 { 3 } else { 2 }) {
+        for elem in row.iter().skip(j.saturating_sub(1)).take(if j > 0 { 3 } else { 2 }) {
+            sum += elem;
+            count += 1;
+        }
+    }
+    sum / count as f32
+}
@@ -0,0 +1,150 @@
+use std::str::FromStr;
+
+// Must match the size of color_map arrays in shaders
+pub const MAX_COLOR_STOPS: usize = 16;
+
+// Name and (height, #rrggbb) stops of built-in palettes
+const PALETTES: &[(&str, &[(f32, u32)])] = &[
+    ("classic", &[(0., 0x00cc00), (0.2, 0x4d3833), (1., 0xe6e6e6)]),
+    ("viridis", &[(0., 0x440154), (0.125, 0x472d7b), (0.25, 0x3b528b), (0.375, 0x2c728e), (0.5, 0x21918c),
+                  (0.625, 0x28ae80), (0.75, 0x5ec962), (0.875, 0xaddc30), (1., 0xfde725)]),
+    ("cividis", &[(0., 0x00224e), (0.25, 0x414d6b), (0.5, 0x7c7b78), (0.75, 0xbcaf6f), (1., 0xfee838)]),
+    ("magma", &[(0., 0x000004), (0.25, 0x51127c), (0.5, 0xb73779), (0.75, 0xfc8961), (1., 0xfcfdbf)]),
+    ("grayscale", &[(0., 0x000000), (1., 0xffffff)]),
+];
+
+// ColorMap uniform block, stops past `size` are zeroed
+#[derive(Std140)]
+pub struct ColorMapBlock {
+    heights: [f32; MAX_COLOR_STOPS],
+    colors: [na::Vector3<f32>; MAX_COLOR_STOPS],
+    size: i32,
+}
+
+// Piecewise linear colour ramp over terrain height [0;1]
+#[derive(Clone, Debug)]
+pub struct ColorMap {
+    pub name: String,
+    pub stops: Vec<(f32, na::Vector3<f32>)>,
+}
+
+impl ColorMap {
+    pub fn classic() -> ColorMap {
+        ColorMap::builtin(PALETTES[0].0).unwrap()
+    }
+
+    pub fn builtin(name: &str) -> Option<ColorMap> {
+        let (name, stops) = PALETTES.iter().find(|(palette, _)| *palette == name)?;
+        Some(ColorMap {
+            name: name.to_string(),
+            stops: stops.iter().map(|(height, rgb)| (*height, hex_to_color(*rgb))).collect(),
+        })
+    }
+
+    // Next built-in palette, custom ramps go back to the first one
+    pub fn next(&self) -> ColorMap {
+        let idx = PALETTES.iter().position(|(palette, _)| *palette == self.name)
+            .map_or(0, |idx| (idx + 1) % PALETTES.len());
+        ColorMap::builtin(PALETTES[idx].0).unwrap()
+    }
+
+    pub fn block(&self) -> ColorMapBlock {
+        let mut block = ColorMapBlock {
+            heights: [0.; MAX_COLOR_STOPS],
+            colors: [na::Vector3::zeros(); MAX_COLOR_STOPS],
+            size: self.stops.len().min(MAX_COLOR_STOPS) as i32,
+        };
+        for (idx, (height, color)) in self.stops.iter().take(MAX_COLOR_STOPS).enumerate() {
+            block.heights[idx] = *height;
+            block.colors[idx] = *color;
+        }
+        block
+    }
+}
+
+impl std::fmt::Display for ColorMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let stops: Vec<String> = self.stops.iter()
+            .map(|(height, color)| format!("{:.3}:{}", height, color_to_hex(color)))
+            .collect();
+        write!(f, "{} [{}]", self.name, stops.join(", "))
+    }
+}
+
+// Either a built-in palette name or a custom list of stops `height:#rrggbb, ...`
+impl FromStr for ColorMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(color_map) = ColorMap::builtin(s) {
+            return Ok(color_map);
+        }
+        if !s.contains(':') {
+            let names: Vec<&str> = PALETTES.iter().map(|(name, _)| *name).collect();
+            return Err(format!("unknown palette {}, expected one of {} or `height:#rrggbb, ...` list", s, names.join(", ")));
+        }
+
+        let mut stops = s.split(',')
+            .map(|stop| {
+                let mut parts = stop.trim().splitn(2, ':');
+                let height = parts.next().unwrap_or("").trim().parse::<f32>()
+                    .map_err(|e| format!("{}: {}", stop, e))?;
+                if height.is_nan() {
+                    return Err(format!("{}: height is not a number", stop));
+                }
+                let color = parts.next().unwrap_or("").trim().trim_start_matches('#');
+                let color = u32::from_str_radix(color, 16)
+                    .map_err(|e| format!("{}: {}", stop, e))?;
+                Ok((height.clamp(0., 1.), hex_to_color(color)))
+            })
+            .collect::<Result<Vec<(f32, na::Vector3<f32>)>, String>>()?;
+
+        if stops.len() < 2 || stops.len() > MAX_COLOR_STOPS {
+            return Err(format!("colour map must have from 2 to {} stops, got {}", MAX_COLOR_STOPS, stops.len()));
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(ColorMap { name: "custom".into(), stops })
+    }
+}
+
+fn hex_to_color(rgb: u32) -> na::Vector3<f32> {
+    na::Vector3::new(
+        ((rgb >> 16) & 0xff) as f32 / 255.,
+        ((rgb >> 8) & 0xff) as f32 / 255.,
+        (rgb & 0xff) as f32 / 255.)
+}
+
+fn color_to_hex(color: &na::Vector3<f32>) -> String {
+    format!("#{:02x}{:02x}{:02x}",
+            (color.x * 255.).round() as u8,
+            (color.y * 255.).round() as u8,
+            (color.z * 255.).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use resources::Resources;
+    use crate::config::Config;
+
+    #[test]
+    fn shipped_config_parses() {
+        let res = Resources::from_path(&Path::new(env!("CARGO_MANIFEST_DIR")).join("assets"));
+        let config = Config::load(&res, "config.cfg").unwrap();
+        config.get_or("terrain_palette", ColorMap::classic()).unwrap();
+    }
+
+    #[test]
+    fn custom_stops_are_not_comments() {
+        let config = Config::parse("terrain_palette = 0:#00cc00, 0.2:#4d3833, 1:#e6e6e6 # classic").unwrap();
+        let color_map = config.get_or("terrain_palette", ColorMap::classic()).unwrap();
+        assert_eq!(color_map.stops.len(), 3);
+        assert_eq!(color_map.stops[1].0, 0.2);
+    }
+
+    #[test]
+    fn nan_stop_is_rejected() {
+        assert!("0:#000000, NaN:#ffffff".parse::<ColorMap>().is_err());
+    }
+}
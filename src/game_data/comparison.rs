@@ -0,0 +1,221 @@
+use gl_render::{self, buffer, data, Viewport};
+use gl_render::text::Text;
+use gl_render::texture::{self, Texture2D, TextureFilter, TextureFormat};
+use resources::Resources;
+use crate::game_data::{GameData, GRID_WIDTH};
+use crate::game_data::brush::Brush;
+use crate::game_data::grid::{Grid, GridingAlgo};
+use crate::game_data::surface::Surface;
+use crate::game_data::water::Water;
+use crate::game_data::render_mode::RenderMode;
+
+const DIFFERENCE_UNIT: gl::types::GLuint = 0;
+const INSET_SIZE: f32 = 0.45;       // of the smaller side of the right half
+const MARGIN: i32 = 10;
+const LABEL_COLOR: (f32, f32, f32, f32) = (1., 1., 1., 1.);
+const LABEL_BACKGROUND: (f32, f32, f32, f32) = (0., 0., 0., 0.5);
+
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = 0]
+    pos: data::f32_f32_f32_f32,     // (x, y) in NDC, (u, v) on the heat map
+}
+
+impl From<(f32, f32, f32, f32)> for Vertex {
+    fn from(elem: (f32, f32, f32, f32)) -> Self {
+        Vertex { pos: elem.into() }
+    }
+}
+
+// Second terrain and water interpolated from the same poles, drawn in the right half of the scene
+// with the main camera, and a top-down heat map of left minus right heights
+pub struct Comparison {
+    pub grid: Grid,
+    pub surface: Surface,
+    pub water: Water,
+    pub difference: bool,
+    gl: gl::Gl,
+    program: gl_render::Program,
+    texture: Texture2D,
+    _vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+}
+
+impl Comparison {
+    pub fn new(res: &Resources, gl: &gl::Gl, grid: &Grid, griding_algo: GridingAlgo) -> Result<Comparison, failure::Error> {
+        let grid = grid.with_griding_algo(griding_algo);
+        let surface = Surface::new(res, gl, grid.get_data())?;
+        let water = Water::new(res, gl, grid.get_data())?;
+        let program = gl_render::Program::from_res(gl, res, "shaders/difference")?;
+
+        let vertices: Vec<Vertex> = vec![
+            (-1., -1., 0., 1.).into(),
+            (1., -1., 1., 1.).into(),
+            (-1., 1., 0., 0.).into(),
+            (1., 1., 1., 0.).into(),
+        ];
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vertices);
+        vbo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        let texture = Texture2D::new(gl, TextureFormat::R32F, TextureFilter::Linear);
+        program.set_uniform("difference", &(DIFFERENCE_UNIT as i32));
+
+        Ok(Comparison { grid, surface, water, difference: true, gl: gl.clone(), program, texture, _vbo: vbo, vao })
+    }
+
+    // Water is flushed as it does not fit the new terrain
+    pub fn regrid(&mut self, griding_algo: GridingAlgo) -> Result<(), failure::Error> {
+        self.water.flush();
+        self.grid.update_grid(GRID_WIDTH, griding_algo);
+        self.water.set_grid(self.grid.get_data())?;
+        self.surface.set_grid(self.grid.get_data())
+    }
+
+    // Follows poles edited on the main terrain
+    pub fn set_poles(&mut self, poles: &[na::Vector3<f32>]) -> Result<(), failure::Error> {
+        self.grid.set_poles(poles);
+        let size = self.grid.get_data().len();
+        self.surface.set_grid(self.grid.get_data())?;
+        self.water.update_borders(self.grid.get_data(), &(0..size, 0..size))?;
+        Ok(())
+    }
+
+    // Strokes on the main terrain are repeated here, so the heat map shows only the algorithms difference
+    pub fn sculpt(&mut self, brush: &mut Brush, row: usize, col: usize) -> Result<(), failure::Error> {
+        let area = self.grid.sculpt(brush, row, col);
+        self.surface.set_grid(self.grid.get_data())?;
+        self.water.update_borders(self.grid.get_data(), &area)?;
+        Ok(())
+    }
+
+    // Water display settings and simulation parameters are shared
+    pub fn follow(&mut self, water: &Water) {
+        self.water.params = water.params;
+        self.water.view = water.view;
+        self.water.set_color(water.color());
+    }
+
+    // Compared terrain and water have their own programs, they are reloaded as well, one result per program
+    pub fn reload_shaders(&mut self, res: &Resources) -> Vec<Result<bool, gl_render::Error>> {
+        let mut results = vec![self.surface.reload_shaders(res)];
+        results.extend(self.water.reload_shaders(res));
+        let difference = self.program.reload_if_changed(res);
+        if let Ok(true) = difference {
+            self.program.set_uniform("difference", &(DIFFERENCE_UNIT as i32));
+        }
+        results.push(difference);
+        results
+    }
+
+    pub fn render(&self, gl: &gl::Gl, surface_mode: RenderMode, water_mode: RenderMode) {
+        surface_mode.render(gl, |mode| self.surface.render(gl, mode));
+        water_mode.render(gl, |mode| self.water.render(gl, mode));
+    }
+
+    // Square at the top left corner of the right half
+    fn difference_rect(right: &Viewport) -> Viewport {
+        let side = (right.w.min(right.h) as f32 * INSET_SIZE) as i32;
+        let mut rect = Viewport::for_window(side, side);
+        rect.x = right.x + MARGIN;
+        rect.y = right.y + right.h - MARGIN - side;
+        rect
+    }
+
+    // Left minus right heights of every grid point, returns the biggest absolute difference
+    fn upload_difference(&self, left: &Grid) -> Result<f32, texture::Error> {
+        let difference: Vec<f32> = left.get_data().iter().flatten()
+            .zip(self.grid.get_data().iter().flatten())
+            .map(|(left, right)| left - right)
+            .collect();
+        let size = self.grid.get_data().len();
+        self.texture.upload(size as i32, size as i32, &difference)?;
+        Ok(difference.iter().fold(0., |max: f32, diff| max.max(diff.abs())))
+    }
+
+    // Difference heat map and algorithm names over both halves, leaves the whole window as viewport
+    pub fn render_overlay(&self, text: &Text, window: &Viewport, left: &Viewport, right: &Viewport, left_grid: &Grid)
+                          -> Result<(), texture::Error> {
+        for (viewport, grid) in [(left, left_grid), (right, &self.grid)] {
+            let label = format!("{:?}, {} edge poles", grid.get_griding_algo(), grid.edge_poles);
+            let (width, height) = text.measure(&label);
+            let x = viewport.x as f32 + (viewport.w as f32 - width) / 2.;
+            let y = (window.h - viewport.y) as f32 - height - MARGIN as f32;
+            text.render(window, &label, x, y, LABEL_COLOR, Some(LABEL_BACKGROUND));
+        }
+        if !self.difference {
+            return Ok(());
+        }
+
+        let max_difference = self.upload_difference(left_grid)?;
+        let rect = Comparison::difference_rect(right);
+        self.program.use_it();
+        self.program.set_uniform("max_difference", &max_difference);
+        rect.use_it(&self.gl);
+        self.texture.bind(DIFFERENCE_UNIT);
+        unsafe {
+            self.gl.Disable(gl::DEPTH_TEST);
+            self.vao.bind();
+            self.gl.DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            self.vao.unbind();
+            self.gl.Enable(gl::DEPTH_TEST);
+        }
+        self.texture.unbind(DIFFERENCE_UNIT);
+        window.use_it(&self.gl);
+
+        let label = format!("left - right, max {:.3}\nred higher, blue lower", max_difference);
+        let (x, y) = (rect.x as f32, (window.h - rect.y) as f32 + 8.);
+        text.render(window, &label, x, y, LABEL_COLOR, Some(LABEL_BACKGROUND));
+        Ok(())
+    }
+}
+
+// Left and right halves of the scene
+pub fn split(scene: &Viewport) -> (Viewport, Viewport) {
+    let mut left = Viewport::for_window(scene.w / 2, scene.h);
+    left.x = scene.x;
+    left.y = scene.y;
+    let mut right = Viewport::for_window(scene.w - left.w, scene.h);
+    right.x = scene.x + left.w;
+    right.y = scene.y;
+    (left, right)
+}
+
+impl GameData {
+    // Applies a water event to the main simulation and the compared one
+    pub(super) fn for_each_water<F: FnMut(&mut Water)>(&mut self, mut event: F) {
+        event(&mut self.water);
+        if let Some(comparison) = self.comparison.as_mut() {
+            event(&mut comparison.water);
+        }
+    }
+
+    // Both waters are flushed to start the comparison again
+    pub(super) fn regrid_comparison(&mut self, griding_algo: GridingAlgo) -> Result<(), failure::Error> {
+        self.water.flush();
+        if let Some(comparison) = self.comparison.as_mut() {
+            comparison.regrid(griding_algo)?;
+        }
+        Ok(())
+    }
+
+    // Right half gets the other algorithm
+    pub(super) fn toggle_comparison(&mut self) -> Result<(), failure::Error> {
+        self.comparison = match self.comparison {
+            Some(_) => None,
+            None => Some(Comparison::new(&self.res, &self.gl, &self.grid, self.grid.get_griding_algo().other())?),
+        };
+        self.update_projection()?;
+        self.apply_color_map()
+    }
+}
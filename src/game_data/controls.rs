@@ -1,9 +1,16 @@
 use failure::err_msg;
 use sdl2::keyboard::Keycode;
 use sdl2::mouse::MouseButton;
-use crate::game_data::{GameData, GRID_WIDTH};
+use crate::game_data::{GameData, GRID_WIDTH, source_resources};
 use crate::game_data::grid::GridingAlgo;
 use crate::game_data::water::Direction;
+use crate::game_data::brush::BrushTool;
+use crate::game_data::poles::Poles;
+
+const EXPORT_STEP: usize = 10;
+const ADD_WATER_RADIUS: usize = 2;
+const POLE_HEIGHT_STEP: f32 = 0.02;
+const FLY_SPEED: f32 = 0.01;
 
 #[derive(PartialEq)]
 #[derive(Copy, Clone)]
@@ -21,6 +28,30 @@ impl From<KeyStatus> for bool {
     }
 }
 
+// What left mouse button does
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum MouseMode {
+    Camera,
+    Sculpt,
+    AddWater,
+    Inspect,
+    Poles,
+}
+
+impl MouseMode {
+    pub fn next(self) -> MouseMode {
+        match self {
+            MouseMode::Camera => MouseMode::Sculpt,
+            MouseMode::Sculpt => MouseMode::AddWater,
+            MouseMode::AddWater => MouseMode::Inspect,
+            MouseMode::Inspect => MouseMode::Poles,
+            MouseMode::Poles => MouseMode::Camera,
+        }
+    }
+}
+
 pub enum Actions {
     Flush,
     AddWater,
@@ -31,6 +62,46 @@ pub enum Actions {
     Rain,
     Kriging,
     RadialBasis,
+    MouseMode,
+    BrushRaise,
+    BrushLower,
+    BrushSmooth,
+    BrushFlatten,
+    BrushBigger,
+    BrushSmaller,
+    Export,
+    PoleUp,
+    PoleDown,
+    PoleDelete,
+    PolesSave,
+    CamReset,
+    Projection,
+    BookmarkAdd,
+    BookmarkGo,
+    PathPlay,
+    SunLeft,
+    SunRight,
+    SunUp,
+    SunDown,
+    Specular,
+    WaterView,
+    SurfaceMode,
+    WaterMode,
+    Palette,
+    Contours,
+    Legend,
+    WaterColor,
+    Screenshot,
+    Record,
+    Hud,
+    Help,
+    Panel,
+    Section,
+    SectionAxis,
+    SectionEnergy,
+    Map,
+    Compare,
+    Difference,
 }
 
 #[derive(Copy, Clone)]
@@ -46,16 +117,75 @@ pub struct Controls {
     pub kriging:        KeyStatus,
     pub radial_basis:   KeyStatus,
     pub is_rain:        bool,
+    pub mouse_mode_key: KeyStatus,
+    pub brush_raise:    KeyStatus,
+    pub brush_lower:    KeyStatus,
+    pub brush_smooth:   KeyStatus,
+    pub brush_flatten:  KeyStatus,
+    pub brush_bigger:   KeyStatus,
+    pub brush_smaller:  KeyStatus,
+    pub export:         KeyStatus,
+    pub pole_up:        KeyStatus,
+    pub pole_down:      KeyStatus,
+    pub pole_delete:    KeyStatus,
+    pub poles_save:     KeyStatus,
+    pub cam_reset:      KeyStatus,
+    pub projection:     KeyStatus,
+    pub fly_forward:    KeyStatus,
+    pub fly_back:       KeyStatus,
+    pub fly_left:       KeyStatus,
+    pub fly_right:      KeyStatus,
+    pub fly_up:         KeyStatus,
+    pub fly_down:       KeyStatus,
+    pub bookmark_add:   KeyStatus,
+    pub bookmark_go:    Option<usize>,
+    pub path_play:      KeyStatus,
+    pub sun_left:       KeyStatus,
+    pub sun_right:      KeyStatus,
+    pub sun_up:         KeyStatus,
+    pub sun_down:       KeyStatus,
+    pub specular:       KeyStatus,
+    pub water_view:     KeyStatus,
+    pub surface_mode:   KeyStatus,
+    pub water_mode:     KeyStatus,
+    pub palette:        KeyStatus,
+    pub contours:       KeyStatus,
+    pub legend:         KeyStatus,
+    pub water_color:    KeyStatus,
+    pub screenshot:     KeyStatus,
+    pub record:         KeyStatus,
+    pub hud:            KeyStatus,
+    pub help:           KeyStatus,
+    pub panel:          KeyStatus,
+    pub section:        KeyStatus,
+    pub section_axis:   KeyStatus,
+    pub section_energy: KeyStatus,
+    pub section_forward: KeyStatus,
+    pub section_back:   KeyStatus,
+    pub map:            KeyStatus,
+    pub compare:        KeyStatus,
+    pub difference:     KeyStatus,
+    pub mouse_mode:     MouseMode,
     pub cam_capture:    KeyStatus,
+    pub tool_capture:   KeyStatus,
+    pub pan_capture:    KeyStatus,
+    pub panel_capture:  KeyStatus,
+    pub panel_rect:     Option<(i32, i32, i32, i32)>,   // (left, top, right, bottom) of the visible panel
+    panel_clk: Option<na::Vector2<i32>>,
     mouse_left_clk: na::Vector2<i32>,
+    mouse_right_clk: na::Vector2<i32>,
+    mouse_wheel: i32,
     mouse_cur_pos: na::Vector2<i32>,
 }
 
 impl Controls {
     pub fn new() -> Controls {
         let mouse_left_clk = na::Vector2::new(0, 0);
+        let mouse_right_clk = na::Vector2::new(0, 0);
+        let mouse_wheel = 0;
         let mouse_cur_pos = na::Vector2::new(0, 0);
         let is_rain = false;
+        let mouse_mode = MouseMode::Camera;
         Controls {
             exit:           KeyStatus::Released,
             flush:          KeyStatus::Released,
@@ -68,8 +198,64 @@ impl Controls {
             radial_basis:   KeyStatus::Released,
             rain:           KeyStatus::Released,
             is_rain,
+            mouse_mode_key: KeyStatus::Released,
+            brush_raise:    KeyStatus::Released,
+            brush_lower:    KeyStatus::Released,
+            brush_smooth:   KeyStatus::Released,
+            brush_flatten:  KeyStatus::Released,
+            brush_bigger:   KeyStatus::Released,
+            brush_smaller:  KeyStatus::Released,
+            export:         KeyStatus::Released,
+            pole_up:        KeyStatus::Released,
+            pole_down:      KeyStatus::Released,
+            pole_delete:    KeyStatus::Released,
+            poles_save:     KeyStatus::Released,
+            cam_reset:      KeyStatus::Released,
+            projection:     KeyStatus::Released,
+            fly_forward:    KeyStatus::Released,
+            fly_back:       KeyStatus::Released,
+            fly_left:       KeyStatus::Released,
+            fly_right:      KeyStatus::Released,
+            fly_up:         KeyStatus::Released,
+            fly_down:       KeyStatus::Released,
+            bookmark_add:   KeyStatus::Released,
+            bookmark_go:    None,
+            path_play:      KeyStatus::Released,
+            sun_left:       KeyStatus::Released,
+            sun_right:      KeyStatus::Released,
+            sun_up:         KeyStatus::Released,
+            sun_down:       KeyStatus::Released,
+            specular:       KeyStatus::Released,
+            water_view:     KeyStatus::Released,
+            surface_mode:   KeyStatus::Released,
+            water_mode:     KeyStatus::Released,
+            palette:        KeyStatus::Released,
+            contours:       KeyStatus::Released,
+            legend:         KeyStatus::Released,
+            water_color:    KeyStatus::Released,
+            screenshot:     KeyStatus::Released,
+            record:         KeyStatus::Released,
+            hud:            KeyStatus::Released,
+            help:           KeyStatus::Released,
+            panel:          KeyStatus::Released,
+            section:        KeyStatus::Released,
+            section_axis:   KeyStatus::Released,
+            section_energy: KeyStatus::Released,
+            section_forward: KeyStatus::Released,
+            section_back:   KeyStatus::Released,
+            map:            KeyStatus::Released,
+            compare:        KeyStatus::Released,
+            difference:     KeyStatus::Released,
+            mouse_mode,
             cam_capture:    KeyStatus::Released,
+            tool_capture:   KeyStatus::Released,
+            pan_capture:    KeyStatus::Released,
+            panel_capture:  KeyStatus::Released,
+            panel_rect:     None,
+            panel_clk:      None,
             mouse_left_clk,
+            mouse_right_clk,
+            mouse_wheel,
             mouse_cur_pos,
         }
     }
@@ -91,12 +277,69 @@ impl Controls {
             Keycode::R =>       self.rain         = status,
             Keycode::Num1 =>    self.radial_basis = status,
             Keycode::Num2 =>    self.kriging      = status,
+            Keycode::T =>       self.mouse_mode_key = status,
+            Keycode::Z =>       self.brush_raise  = status,
+            Keycode::X =>       self.brush_lower  = status,
+            Keycode::C =>       self.brush_smooth = status,
+            Keycode::V =>       self.brush_flatten = status,
+            Keycode::RightBracket => self.brush_bigger = status,
+            Keycode::LeftBracket =>  self.brush_smaller = status,
+            Keycode::E =>       self.export       = status,
+            Keycode::Up =>      self.pole_up      = status,
+            Keycode::Down =>    self.pole_down    = status,
+            Keycode::Delete | Keycode::Backspace => self.pole_delete = status,
+            Keycode::P =>       self.poles_save   = status,
+            Keycode::H =>       self.cam_reset    = status,
+            Keycode::N =>       self.projection   = status,
+            Keycode::I =>       self.fly_forward  = status,
+            Keycode::K =>       self.fly_back     = status,
+            Keycode::J =>       self.fly_left     = status,
+            Keycode::L =>       self.fly_right    = status,
+            Keycode::O =>       self.fly_up       = status,
+            Keycode::U =>       self.fly_down     = status,
+            Keycode::B =>       self.bookmark_add = status,
+            Keycode::G =>       self.path_play    = status,
+            Keycode::Comma =>   self.sun_left     = status,
+            Keycode::Period =>  self.sun_right    = status,
+            Keycode::Quote =>   self.sun_up       = status,
+            Keycode::Semicolon => self.sun_down   = status,
+            Keycode::Slash =>   self.specular     = status,
+            Keycode::M =>       self.water_view   = status,
+            Keycode::Num3 =>    self.surface_mode = status,
+            Keycode::Num4 =>    self.water_mode   = status,
+            Keycode::Num5 =>    self.palette      = status,
+            Keycode::Num6 =>    self.contours     = status,
+            Keycode::Num7 =>    self.legend       = status,
+            Keycode::Num8 =>    self.water_color  = status,
+            Keycode::F12 =>     self.screenshot   = status,
+            Keycode::F11 =>     self.record       = status,
+            Keycode::F10 =>     self.hud          = status,
+            Keycode::Tab =>     self.help         = status,
+            Keycode::Num9 =>    self.panel        = status,
+            Keycode::Num0 =>    self.section      = status,
+            Keycode::Y =>       self.section_axis = status,
+            Keycode::Equals =>  self.section_energy = status,
+            Keycode::End =>     self.section_forward = status,
+            Keycode::Home =>    self.section_back = status,
+            Keycode::Minus =>   self.map          = status,
+            Keycode::Backslash => self.compare    = status,
+            Keycode::Backquote => self.difference = status,
+            Keycode::F1 | Keycode::F2 | Keycode::F3 | Keycode::F4 | Keycode::F5 |
+            Keycode::F6 | Keycode::F7 | Keycode::F8 | Keycode::F9 if status == KeyStatus::Pressed =>
+                self.bookmark_go = Some(key as usize - Keycode::F1 as usize),
             _ => (),
         }
     }
 
     pub fn action_mouse(&mut self, key: MouseButton, x: i32, y: i32, status: KeyStatus) {
         match key {
+            // Panel takes clicks over it and keeps the button until it is released
+            MouseButton::Left if status == KeyStatus::Pressed && self.is_over_panel(x, y) => {
+                self.panel_capture = status;
+                self.panel_clk = Some(na::Vector2::new(x, y));
+            },
+            MouseButton::Left if self.panel_capture == KeyStatus::Pressed => self.panel_capture = status,
+            MouseButton::Left if self.mouse_mode != MouseMode::Camera => self.tool_capture = status,
             MouseButton::Left => {
                 self.cam_capture = status;
                 if status == KeyStatus::Pressed {
@@ -104,10 +347,28 @@ impl Controls {
                     self.mouse_left_clk.y = y;
                 }
             },
+            MouseButton::Right => {
+                self.pan_capture = status;
+                if status == KeyStatus::Pressed {
+                    self.mouse_right_clk.x = x;
+                    self.mouse_right_clk.y = y;
+                }
+            },
             _ => (),
         }
     }
 
+    fn is_over_panel(&self, x: i32, y: i32) -> bool {
+        match self.panel_rect {
+            Some((left, top, right, bottom)) => x >= left && x < right && y >= top && y < bottom,
+            None => false,
+        }
+    }
+
+    pub fn action_mouse_wheel(&mut self, y: i32) {
+        self.mouse_wheel += y;
+    }
+
     pub fn action_mouse_move(&mut self, x: i32, y: i32) {
         self.mouse_cur_pos.x = x;
         self.mouse_cur_pos.y = y;
@@ -124,6 +385,46 @@ impl Controls {
             Actions::Rain        => self.rain         = KeyStatus::Released,
             Actions::Kriging     => self.kriging      = KeyStatus::Released,
             Actions::RadialBasis => self.radial_basis = KeyStatus::Released,
+            Actions::MouseMode   => self.mouse_mode_key = KeyStatus::Released,
+            Actions::BrushRaise  => self.brush_raise  = KeyStatus::Released,
+            Actions::BrushLower  => self.brush_lower  = KeyStatus::Released,
+            Actions::BrushSmooth => self.brush_smooth = KeyStatus::Released,
+            Actions::BrushFlatten => self.brush_flatten = KeyStatus::Released,
+            Actions::BrushBigger => self.brush_bigger = KeyStatus::Released,
+            Actions::BrushSmaller => self.brush_smaller = KeyStatus::Released,
+            Actions::Export      => self.export       = KeyStatus::Released,
+            Actions::PoleUp      => self.pole_up      = KeyStatus::Released,
+            Actions::PoleDown    => self.pole_down    = KeyStatus::Released,
+            Actions::PoleDelete  => self.pole_delete  = KeyStatus::Released,
+            Actions::PolesSave   => self.poles_save   = KeyStatus::Released,
+            Actions::CamReset    => self.cam_reset    = KeyStatus::Released,
+            Actions::Projection  => self.projection   = KeyStatus::Released,
+            Actions::BookmarkAdd => self.bookmark_add = KeyStatus::Released,
+            Actions::BookmarkGo  => self.bookmark_go  = None,
+            Actions::PathPlay    => self.path_play    = KeyStatus::Released,
+            Actions::SunLeft     => self.sun_left     = KeyStatus::Released,
+            Actions::SunRight    => self.sun_right    = KeyStatus::Released,
+            Actions::SunUp       => self.sun_up       = KeyStatus::Released,
+            Actions::SunDown     => self.sun_down     = KeyStatus::Released,
+            Actions::Specular    => self.specular     = KeyStatus::Released,
+            Actions::WaterView   => self.water_view   = KeyStatus::Released,
+            Actions::SurfaceMode => self.surface_mode = KeyStatus::Released,
+            Actions::WaterMode   => self.water_mode   = KeyStatus::Released,
+            Actions::Palette     => self.palette      = KeyStatus::Released,
+            Actions::Contours    => self.contours     = KeyStatus::Released,
+            Actions::Legend      => self.legend       = KeyStatus::Released,
+            Actions::WaterColor  => self.water_color  = KeyStatus::Released,
+            Actions::Screenshot  => self.screenshot   = KeyStatus::Released,
+            Actions::Record      => self.record       = KeyStatus::Released,
+            Actions::Hud         => self.hud          = KeyStatus::Released,
+            Actions::Help        => self.help         = KeyStatus::Released,
+            Actions::Panel       => self.panel        = KeyStatus::Released,
+            Actions::Section     => self.section      = KeyStatus::Released,
+            Actions::SectionAxis => self.section_axis = KeyStatus::Released,
+            Actions::SectionEnergy => self.section_energy = KeyStatus::Released,
+            Actions::Map         => self.map          = KeyStatus::Released,
+            Actions::Compare     => self.compare      = KeyStatus::Released,
+            Actions::Difference  => self.difference   = KeyStatus::Released,
         }
     }
 
@@ -131,6 +432,41 @@ impl Controls {
         self.mouse_cur_pos - self.mouse_left_clk
     }
 
+    pub fn get_pan(&self) -> na::Vector2<i32> {
+        self.mouse_cur_pos - self.mouse_right_clk
+    }
+
+    pub fn save_mouse_right_clk_pos(&mut self) {
+        self.mouse_right_clk.x = self.mouse_cur_pos.x;
+        self.mouse_right_clk.y = self.mouse_cur_pos.y;
+    }
+
+    pub fn take_panel_click(&mut self) -> Option<na::Vector2<i32>> {
+        self.panel_clk.take()
+    }
+
+    pub fn take_mouse_wheel(&mut self) -> i32 {
+        std::mem::replace(&mut self.mouse_wheel, 0)
+    }
+
+    // Held fly keys as (right, up, forward) direction
+    pub fn get_fly_direction(&self) -> na::Vector3<f32> {
+        let axis = |pos: KeyStatus, neg: KeyStatus| bool::from(pos) as i32 as f32 - bool::from(neg) as i32 as f32;
+        na::Vector3::new(
+            axis(self.fly_right, self.fly_left),
+            axis(self.fly_up, self.fly_down),
+            axis(self.fly_forward, self.fly_back))
+    }
+
+    // Held section keys as -1, 0 or 1 cell per frame
+    pub fn get_section_shift(&self) -> i32 {
+        bool::from(self.section_forward) as i32 - bool::from(self.section_back) as i32
+    }
+
+    pub fn get_mouse_pos(&self) -> na::Vector2<i32> {
+        self.mouse_cur_pos
+    }
+
     pub fn save_mouse_clk_pos(&mut self) {
         self.mouse_left_clk.x = self.mouse_cur_pos.x;
         self.mouse_left_clk.y = self.mouse_cur_pos.y;
@@ -149,44 +485,92 @@ impl GameData {
         if self.controls.wave_w.into() { self.action_wave_w() };
         if self.controls.wave_e.into() { self.action_wave_e() };
         if self.controls.rain.into() { self.action_rain() };
+        if self.controls.mouse_mode_key.into() { self.action_mouse_mode() };
+        if self.controls.brush_raise.into() { self.action_brush_tool(Actions::BrushRaise, BrushTool::Raise) };
+        if self.controls.brush_lower.into() { self.action_brush_tool(Actions::BrushLower, BrushTool::Lower) };
+        if self.controls.brush_smooth.into() { self.action_brush_tool(Actions::BrushSmooth, BrushTool::Smooth) };
+        if self.controls.brush_flatten.into() { self.action_brush_tool(Actions::BrushFlatten, BrushTool::Flatten) };
+        if self.controls.brush_bigger.into() { self.action_brush_bigger() };
+        if self.controls.brush_smaller.into() { self.action_brush_smaller() };
+        if self.controls.export.into() { self.action_export()? };
+        if self.controls.pole_up.into() { self.action_pole_height(Actions::PoleUp, POLE_HEIGHT_STEP)? };
+        if self.controls.pole_down.into() { self.action_pole_height(Actions::PoleDown, -POLE_HEIGHT_STEP)? };
+        if self.controls.pole_delete.into() { self.action_pole_delete()? };
+        if self.controls.poles_save.into() { self.action_poles_save()? };
         if self.controls.cam_capture.into() { self.action_cam_capture().map_err(err_msg)? };
+        if self.controls.pan_capture.into() { self.action_cam_pan()? };
+        if self.controls.cam_reset.into() { self.action_cam_reset()? };
+        if self.controls.projection.into() { self.action_projection()? };
+        if self.controls.bookmark_add.into() { self.action_bookmark_add()? };
+        if let Some(idx) = self.controls.bookmark_go { self.action_bookmark_go(idx) };
+        if self.controls.path_play.into() { self.action_path_play() };
+        if self.controls.sun_left.into() { self.action_sun(Actions::SunLeft)? };
+        if self.controls.sun_right.into() { self.action_sun(Actions::SunRight)? };
+        if self.controls.sun_up.into() { self.action_sun(Actions::SunUp)? };
+        if self.controls.sun_down.into() { self.action_sun(Actions::SunDown)? };
+        if self.controls.specular.into() { self.action_specular()? };
+        if self.controls.water_view.into() { self.action_water_view() };
+        if self.controls.surface_mode.into() { self.action_surface_mode() };
+        if self.controls.water_mode.into() { self.action_water_mode() };
+        if self.controls.palette.into() { self.action_palette()? };
+        if self.controls.contours.into() { self.action_contours()? };
+        if self.controls.legend.into() { self.action_legend() };
+        if self.controls.water_color.into() { self.action_water_color() };
+        if self.controls.screenshot.into() { self.action_screenshot() };
+        if self.controls.record.into() { self.action_record()? };
+        if self.controls.hud.into() { self.action_hud() };
+        if self.controls.help.into() { self.action_help() };
+        if self.controls.panel.into() { self.action_panel() };
+        if self.controls.section.into() { self.action_section()? };
+        if self.controls.section_axis.into() { self.action_section_axis() };
+        if self.controls.section_energy.into() { self.action_section_energy() };
+        self.action_section_move();
+        if self.controls.map.into() { self.action_map() };
+        if self.controls.compare.into() { self.action_compare()? };
+        if self.controls.difference.into() { self.action_difference() };
+        self.update_panel()?;
+        self.action_cam_zoom()?;
+        self.action_cam_fly()?;
+        if self.controls.tool_capture.into() { self.action_tool()? };
+        if self.controls.tool_capture == KeyStatus::Released { self.end_tool_stroke() };
+        if let Some(comparison) = self.comparison.as_mut() { comparison.follow(&self.water) };
         Ok(())
     }
 
-    fn action_flush(&mut self) {
+    pub(super) fn action_flush(&mut self) {
         println!("Flush!");
         self.controls.reset_action(Actions::Flush);
-        self.water.flush();
+        self.for_each_water(|water| water.flush());
     }
 
     fn action_add_water(&mut self) {
         println!("Add water");
         self.controls.reset_action(Actions::AddWater);
-        self.water.increase_water_level();
+        self.for_each_water(|water| water.increase_water_level());
     }
 
     fn action_wave_s(&mut self) {
         println!("Wave south");
         self.controls.reset_action(Actions::WaveS);
-        self.water.add_wave_particles(Direction::South);
+        self.for_each_water(|water| water.add_wave_particles(Direction::South));
     }
 
     fn action_wave_n(&mut self) {
         println!("Wave north");
         self.controls.reset_action(Actions::WaveN);
-        self.water.add_wave_particles(Direction::North);
+        self.for_each_water(|water| water.add_wave_particles(Direction::North));
     }
 
     fn action_wave_e(&mut self) {
         println!("Wave east");
         self.controls.reset_action(Actions::WaveE);
-        self.water.add_wave_particles(Direction::East);
+        self.for_each_water(|water| water.add_wave_particles(Direction::East));
     }
 
     fn action_wave_w(&mut self) {
         println!("Wave west");
         self.controls.reset_action(Actions::WaveW);
-        self.water.add_wave_particles(Direction::West);
+        self.for_each_water(|water| water.add_wave_particles(Direction::West));
     }
 
     fn action_rain(&mut self) {
@@ -198,22 +582,22 @@ impl GameData {
         }
     }
 
-    fn action_set_kriging(&mut self) -> Result<(), failure::Error> {
+    pub(super) fn action_set_kriging(&mut self) -> Result<(), failure::Error> {
         println!("Griding algorithm: Kriging");
         self.controls.reset_action(Actions::Kriging);
         self.action_flush();
         self.grid.update_grid(GRID_WIDTH, GridingAlgo::Kriging);
-        self.water.set_grid(&self.grid.get_data());
+        self.water.set_grid(self.grid.get_data())?;
         self.surface.set_grid(&self.grid.get_data())?;
         Ok(())
     }
 
-    fn action_set_radial_basis(&mut self) -> Result<(), failure::Error> {
+    pub(super) fn action_set_radial_basis(&mut self) -> Result<(), failure::Error> {
         println!("Griding algorithm: Radial basis function");
         self.controls.reset_action(Actions::RadialBasis);
         self.action_flush();
         self.grid.update_grid(GRID_WIDTH, GridingAlgo::RadialBasisFunction);
-        self.water.set_grid(&self.grid.get_data());
+        self.water.set_grid(self.grid.get_data())?;
         self.surface.set_grid(&self.grid.get_data())?;
         Ok(())
     }
@@ -234,6 +618,397 @@ impl GameData {
         Ok(())
     }
 
+    fn action_mouse_mode(&mut self) {
+        self.controls.reset_action(Actions::MouseMode);
+        self.controls.mouse_mode = self.controls.mouse_mode.next();
+        self.controls.tool_capture = KeyStatus::Released;
+        self.controls.cam_capture = KeyStatus::Released;
+        println!("Mouse mode: {:?}", self.controls.mouse_mode);
+    }
+
+    fn action_tool(&mut self) -> Result<(), failure::Error> {
+        match self.controls.mouse_mode {
+            MouseMode::Camera => (),
+            MouseMode::Sculpt => self.action_sculpt()?,
+            MouseMode::AddWater => self.action_add_water_at(),
+            MouseMode::Inspect => self.action_inspect(),
+            MouseMode::Poles => self.action_poles()?,
+        }
+        Ok(())
+    }
+
+    fn end_tool_stroke(&mut self) {
+        self.brush.end_stroke();
+        self.poles.dragging = false;
+    }
+
+    fn action_brush_tool(&mut self, action: Actions, tool: BrushTool) {
+        self.controls.reset_action(action);
+        self.brush.tool = tool;
+        println!("Brush: {:?}", tool);
+    }
+
+    fn action_brush_bigger(&mut self) {
+        self.controls.reset_action(Actions::BrushBigger);
+        self.brush.increase_radius();
+        println!("Brush radius: {}", self.brush.radius);
+    }
+
+    fn action_brush_smaller(&mut self) {
+        self.controls.reset_action(Actions::BrushSmaller);
+        self.brush.decrease_radius();
+        println!("Brush radius: {}", self.brush.radius);
+    }
+
+    fn action_sculpt(&mut self) -> Result<(), failure::Error> {
+        let (row, col) = match self.pick_surface() {
+            Some(cell) => cell,
+            None => return Ok(()),
+        };
+        let area = self.grid.sculpt(&mut self.brush, row, col);
+        self.surface.set_grid(self.grid.get_data())?;
+        self.water.update_borders(self.grid.get_data(), &area)?;
+        if let Some(comparison) = self.comparison.as_mut() {
+            comparison.sculpt(&mut self.brush, row, col)?;
+        }
+        Ok(())
+    }
+
+    fn action_add_water_at(&mut self) {
+        if let Some(info) = self.pick_cell() {
+            self.for_each_water(|water| water.add_water_at(info.cell.x, info.cell.z, ADD_WATER_RADIUS));
+        }
+    }
+
+    fn action_inspect(&mut self) {
+        self.controls.tool_capture = KeyStatus::Released;
+        match self.pick_cell() {
+            Some(info) => println!("Cell (x: {}, y: {}, z: {}): height {:.3}, water depth {}",
+                                   info.cell.x, info.cell.y, info.cell.z, info.height, info.depth),
+            None => println!("Nothing under cursor"),
+        }
+    }
+
+    // Click selects pole under cursor or adds a new one on the surface, holding drags it over x/z
+    fn action_poles(&mut self) -> Result<(), failure::Error> {
+        if !self.poles.dragging {
+            self.poles.dragging = true;
+            let picked = Poles::pick(self.grid.get_poles(), &self.mvp, &self.scene_viewport(), self.scene_cursor());
+            self.poles.selected = match (picked, self.pick_surface()) {
+                (Some(idx), _) => Some(idx),
+                (None, Some((row, col))) => {
+                    let idx = self.grid.add_pole(self.grid.cell_to_point(row, col));
+                    self.poles.selected = Some(idx);
+                    self.action_poles_changed()?;
+                    Some(idx)
+                },
+                (None, None) => None,
+            };
+            self.poles.set_poles(self.grid.get_poles());
+            return Ok(())
+        }
+
+        let idx = match self.poles.selected {
+            Some(idx) => idx,
+            None => return Ok(()),
+        };
+        let pole = self.grid.get_poles()[idx];
+        let (origin, dir) = match self.cursor_ray() {
+            Some(ray) if ray.1.y.abs() > f32::EPSILON => ray,
+            _ => return Ok(()),
+        };
+        let point = origin + dir * ((pole.y - origin.y) / dir.y);
+        if (point.x - pole.x).abs() < f32::EPSILON && (point.z - pole.z).abs() < f32::EPSILON {
+            return Ok(())
+        }
+        self.grid.move_pole(idx, na::Vector3::new(point.x, pole.y, point.z));
+        self.action_poles_changed()
+    }
+
+    fn action_pole_height(&mut self, action: Actions, delta: f32) -> Result<(), failure::Error> {
+        self.controls.reset_action(action);
+        if let Some(idx) = self.poles.selected {
+            let pole = self.grid.get_poles()[idx];
+            self.grid.move_pole(idx, pole + na::Vector3::y() * delta);
+            self.action_poles_changed()?;
+        }
+        Ok(())
+    }
+
+    fn action_pole_delete(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::PoleDelete);
+        if let Some(idx) = self.poles.selected.take() {
+            self.grid.remove_pole(idx);
+            self.action_poles_changed()?;
+        }
+        Ok(())
+    }
+
+    fn action_poles_save(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::PolesSave);
+        let res = source_resources(&self.res);
+        self.grid.save_poles(&res)?;
+        println!("Poles saved to {}", res.path(self.grid.get_path()).display());
+        Ok(())
+    }
+
+    pub(super) fn action_poles_changed(&mut self) -> Result<(), failure::Error> {
+        let size = self.grid.get_data().len();
+        self.surface.set_grid(self.grid.get_data())?;
+        self.water.update_borders(self.grid.get_data(), &(0..size, 0..size))?;
+        self.poles.set_poles(self.grid.get_poles());
+        if let Some(comparison) = self.comparison.as_mut() {
+            comparison.set_poles(self.grid.get_poles())?;
+        }
+        Ok(())
+    }
+
+    fn action_export(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::Export);
+        let name = format!("export_{}.mod1", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+        let res = source_resources(&self.res);
+        self.grid.export(&res, &name, EXPORT_STEP)?;
+        println!("Terrain exported to {}", res.path(&format!("grids/{}", name)).display());
+        Ok(())
+    }
+
+    fn action_cam_pan(&mut self) -> Result<(), failure::Error> {
+        let pan: na::Vector2<i32> = self.controls.get_pan();
+        self.controls.save_mouse_right_clk_pos();
+        if pan.x == 0 && pan.y == 0 {
+            return Ok(())
+        }
+
+        let scene = self.scene_viewport();
+        let pan: na::Vector2<f32> = na::Vector2::new(
+            (pan.x) as f32 / (scene.w) as f32,
+            (pan.y) as f32 / (scene.h) as f32 );
+
+        self.mvp.pan(pan);
+        self.apply_uniforms()
+    }
+
+    fn action_cam_zoom(&mut self) -> Result<(), failure::Error> {
+        let steps = self.controls.take_mouse_wheel();
+        if steps == 0 {
+            return Ok(())
+        }
+        self.mvp.zoom(steps);
+        self.apply_uniforms()
+    }
+
+    fn action_cam_fly(&mut self) -> Result<(), failure::Error> {
+        let direction = self.controls.get_fly_direction();
+        if direction == na::Vector3::zeros() {
+            return Ok(())
+        }
+        self.mvp.fly(direction * FLY_SPEED);
+        self.apply_uniforms()
+    }
+
+    fn action_cam_reset(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::CamReset);
+        self.mvp.reset();
+        self.apply_uniforms()
+    }
+
+    fn action_projection(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::Projection);
+        self.mvp.toggle_projection();
+        println!("Projection: {:?}", self.mvp.get_projection_mode());
+        self.apply_uniforms()
+    }
+
+    fn action_bookmark_add(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::BookmarkAdd);
+        let res = source_resources(&self.res);
+        let name = self.camera_path.add_bookmark(&res, self.mvp.get_pose())?.name.clone();
+        println!("Bookmark {} saved to {}", name, res.path(self.camera_path.get_path()).display());
+        Ok(())
+    }
+
+    fn action_bookmark_go(&mut self, idx: usize) {
+        self.controls.reset_action(Actions::BookmarkGo);
+        match self.camera_path.go_to(idx, self.mvp.get_pose()) {
+            Some(bookmark) => println!("Go to bookmark {}", bookmark.name),
+            None => println!("No bookmark F{}", idx + 1),
+        }
+    }
+
+    fn action_path_play(&mut self) {
+        self.controls.reset_action(Actions::PathPlay);
+        if self.camera_path.is_playing() {
+            self.camera_path.stop();
+            println!("Camera path stop");
+        }
+        else if self.camera_path.play() {
+            println!("Camera path start");
+        }
+        else {
+            println!("Camera path needs at least 2 bookmarks");
+        }
+    }
+
+    fn action_sun(&mut self, action: Actions) -> Result<(), failure::Error> {
+        match action {
+            Actions::SunLeft => self.light.rotate_azimuth(-1.),
+            Actions::SunRight => self.light.rotate_azimuth(1.),
+            Actions::SunUp => self.light.rotate_elevation(1.),
+            Actions::SunDown => self.light.rotate_elevation(-1.),
+            _ => (),
+        }
+        self.controls.reset_action(action);
+        println!("Sun azimuth: {:.0}, elevation: {:.0}",
+                 self.light.azimuth.to_degrees(), self.light.elevation.to_degrees());
+        self.apply_uniforms()
+    }
+
+    fn action_specular(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::Specular);
+        self.light.specular = !self.light.specular;
+        match self.light.specular {
+            true => println!("Specular on"),
+            false => println!("Specular off"),
+        }
+        self.apply_uniforms()
+    }
+
+    fn action_water_view(&mut self) {
+        self.controls.reset_action(Actions::WaterView);
+        self.water.toggle_view();
+        println!("Water view: {:?}", self.water.view);
+    }
+
+    fn action_surface_mode(&mut self) {
+        self.controls.reset_action(Actions::SurfaceMode);
+        self.surface_mode = self.surface_mode.next();
+        println!("Surface render mode: {:?}", self.surface_mode);
+    }
+
+    fn action_water_mode(&mut self) {
+        self.controls.reset_action(Actions::WaterMode);
+        self.water_mode = self.water_mode.next();
+        println!("Water render mode: {:?}", self.water_mode);
+    }
+
+    pub(super) fn action_palette(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::Palette);
+        self.color_map = self.color_map.next();
+        println!("Terrain palette: {}", self.color_map);
+        self.apply_color_map()
+    }
+
+    fn action_contours(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::Contours);
+        self.contours = !self.contours;
+        match self.contours {
+            true => println!("Contours every {} of height", self.contour_interval),
+            false => println!("Contours off"),
+        }
+        self.apply_color_map()
+    }
+
+    fn action_legend(&mut self) {
+        self.controls.reset_action(Actions::Legend);
+        self.legend.toggle();
+        match self.legend.visible {
+            true => println!("Legend: {}", self.color_map),
+            false => println!("Legend off"),
+        }
+    }
+
+    fn action_water_color(&mut self) {
+        self.controls.reset_action(Actions::WaterColor);
+        self.water.set_color(self.water.color().next());
+        println!("Water particles coloured by: {:?}", self.water.color());
+    }
+
+    // Picture is taken from the next rendered frame
+    fn action_screenshot(&mut self) {
+        self.controls.reset_action(Actions::Screenshot);
+        self.screenshot = true;
+    }
+
+    fn action_record(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::Record);
+        match self.recorder.is_recording() {
+            true => self.stop_recording(),
+            false => println!("Recording to {}", self.recorder.start(self.viewport.w, self.viewport.h)?),
+        }
+        Ok(())
+    }
+
+    fn action_hud(&mut self) {
+        self.controls.reset_action(Actions::Hud);
+        self.hud.visible = !self.hud.visible;
+    }
+
+    // Help is a part of HUD, so it shows HUD too
+    fn action_help(&mut self) {
+        self.controls.reset_action(Actions::Help);
+        self.hud.help = !self.hud.help;
+        self.hud.visible |= self.hud.help;
+    }
+
+    fn action_panel(&mut self) {
+        self.controls.reset_action(Actions::Panel);
+        self.panel.toggle();
+    }
+
+    fn action_section(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::Section);
+        self.section.layout = self.section.layout.next();
+        println!("Section: {:?}", self.section.layout);
+        self.update_projection()
+    }
+
+    fn action_section_axis(&mut self) {
+        self.controls.reset_action(Actions::SectionAxis);
+        self.section.axis = self.section.axis.next();
+        println!("Section along {:?}", self.section.axis);
+    }
+
+    fn action_section_energy(&mut self) {
+        self.controls.reset_action(Actions::SectionEnergy);
+        self.section.energy = !self.section.energy;
+        match self.section.energy {
+            true => println!("Section water coloured by energy"),
+            false => println!("Section water plain"),
+        }
+    }
+
+    fn action_section_move(&mut self) {
+        let shift = self.controls.get_section_shift();
+        if shift != 0 {
+            self.section.move_by(shift, self.water.section_size().0);
+        }
+    }
+
+    fn action_map(&mut self) {
+        self.controls.reset_action(Actions::Map);
+        self.map.layout = self.map.layout.next();
+        println!("Map: {:?}", self.map.layout);
+    }
+
+    fn action_compare(&mut self) -> Result<(), failure::Error> {
+        self.controls.reset_action(Actions::Compare);
+        self.toggle_comparison()?;
+        match &self.comparison {
+            Some(comparison) => println!("Comparing {:?} with {:?}",
+                                         self.grid.get_griding_algo(), comparison.grid.get_griding_algo()),
+            None => println!("Comparison off"),
+        }
+        Ok(())
+    }
+
+    fn action_difference(&mut self) {
+        self.controls.reset_action(Actions::Difference);
+        match self.comparison.as_mut() {
+            Some(comparison) => comparison.difference = !comparison.difference,
+            None => println!("Difference map needs comparison mode"),
+        }
+    }
+
     fn action_exit(&mut self) {
         self.need_exit = true;
     }
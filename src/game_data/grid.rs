@@ -1,17 +1,36 @@
 use resources::Resources;
 use failure::err_msg;
 use std::ffi::CString;
+use crate::game_data::brush::{Brush, BrushArea};
+use crate::game_data::picking::clip_to_domain;
 
 pub struct Grid {
+    pub edge_poles: usize,      // zero height poles added along every side
+    path: String,
     poles: Vec<na::Vector3<f32>>,
+    griding_algo: GridingAlgo,
     data: Vec<Vec<f32>>,
 }
 
+const EDGE_POLES: usize = 30;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
 pub enum GridingAlgo {
     RadialBasisFunction,
     Kriging,
 }
 
+impl GridingAlgo {
+    pub fn other(self) -> GridingAlgo {
+        match self {
+            GridingAlgo::RadialBasisFunction => GridingAlgo::Kriging,
+            GridingAlgo::Kriging => GridingAlgo::RadialBasisFunction,
+        }
+    }
+}
+
 #[derive(Fail, Debug)]
 pub enum Error {
     #[fail(display = "Unable to convert file {} to string", name)]
@@ -31,22 +50,129 @@ pub enum Error {
 impl Grid {
     pub fn new(res: &Resources, grid_path: &str, size: usize, griding_algo: GridingAlgo) -> Result<Grid, failure::Error> {
         let input_array = Grid::get_user_grid(res, grid_path)?;
-        let input_array = Grid::add_zeros_to_edges(&input_array, 30);    // TODO: config
-        let grid = Grid::make_grid(size, &input_array, griding_algo);
-        Ok(Grid {
+        let mut grid = Grid {
+            edge_poles: EDGE_POLES,
+            path: grid_path.to_owned(),
             poles: input_array,
-            data: grid,
-        })
+            griding_algo,
+            data: vec![],
+        };
+        grid.update_grid(size, griding_algo);
+        Ok(grid)
+    }
+
+    // Same poles interpolated with another algorithm
+    pub fn with_griding_algo(&self, griding_algo: GridingAlgo) -> Grid {
+        let mut grid = Grid {
+            edge_poles: self.edge_poles,
+            path: self.path.clone(),
+            poles: self.poles.clone(),
+            griding_algo,
+            data: vec![],
+        };
+        grid.update_grid(self.data.len(), griding_algo);
+        grid
     }
 
     pub fn update_grid(&mut self, size: usize, griding_algo: GridingAlgo) {
-        self.data = Grid::make_grid(size, &self.poles, griding_algo);
+        let input_array = Grid::add_zeros_to_edges(&self.poles, self.edge_poles as i32);
+        self.griding_algo = griding_algo;
+        self.data = Grid::make_grid(size, &input_array, griding_algo);
+    }
+
+    pub fn get_griding_algo(&self) -> GridingAlgo {
+        self.griding_algo
+    }
+
+    pub fn get_poles(&self) -> &Vec<na::Vector3<f32>> {
+        &self.poles
+    }
+
+    pub fn add_pole(&mut self, pole: na::Vector3<f32>) -> usize {
+        self.poles.push(clamp_pole(pole));
+        self.update_grid(self.data.len(), self.griding_algo);
+        self.poles.len() - 1
+    }
+
+    pub fn move_pole(&mut self, idx: usize, pole: na::Vector3<f32>) {
+        self.poles[idx] = clamp_pole(pole);
+        self.update_grid(self.data.len(), self.griding_algo);
+    }
+
+    pub fn remove_pole(&mut self, idx: usize) {
+        self.poles.remove(idx);
+        self.update_grid(self.data.len(), self.griding_algo);
+    }
+
+    pub fn set_poles(&mut self, poles: &[na::Vector3<f32>]) {
+        self.poles = poles.to_vec();
+        self.update_grid(self.data.len(), self.griding_algo);
+    }
+
+    // Writes poles to the path they were loaded from, under `res`
+    pub fn save_poles(&self, res: &Resources) -> Result<(), failure::Error> {
+        res.save_string(&self.path, &poles_to_mod1(&self.poles)).map_err(err_msg)?;
+        Ok(())
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
     }
 
     pub fn get_data(&self) -> &Vec<Vec<f32>> {
         &self.data
     }
 
+    pub fn sculpt(&mut self, brush: &mut Brush, row: usize, col: usize) -> BrushArea {
+        brush.apply(&mut self.data, row, col)
+    }
+
+    // Saves every `step`-th grid point as .mod1 poles to grids/`name`
+    pub fn export(&self, res: &Resources, name: &str, step: usize) -> Result<(), failure::Error> {
+        let size = self.data.len();
+        let mut points: Vec<na::Vector3<f32>> = vec![];
+
+        for (i, row) in self.data.iter().enumerate().step_by(step) {
+            for (j, elem) in row.iter().enumerate().step_by(step) {
+                let point = na::Vector3::new(grid_coord(j, size), *elem, grid_coord(i, size));
+                points.push(clamp_pole(point));
+            }
+        }
+        res.save_string(&format!("grids/{}", name), &poles_to_mod1(&points)).map_err(err_msg)?;
+        Ok(())
+    }
+
+    // Finds (row, col) of the rendered grid point hit by ray origin + t * dir, t in [0;1]
+    pub fn ray_cast(&self, origin: &na::Point3<f32>, dir: &na::Vector3<f32>) -> Option<(usize, usize)> {
+        let size = self.data.len();
+        let step = 2. / (size - 1) as f32;
+        let (t_min, t_max) = clip_to_domain(origin, dir)?;
+        let t_step = step / 2. / dir.norm();
+
+        let mut t = t_min;
+        while t <= t_max {
+            let point = origin + dir * t;
+            let (row, col) = self.point_to_cell(&point);
+            if point.y <= self.data[row][col] {
+                return Some((row, col));
+            }
+            t += t_step;
+        }
+        None
+    }
+
+    // Where grid point (row, col) is sampled and drawn
+    pub fn cell_to_point(&self, row: usize, col: usize) -> na::Vector3<f32> {
+        let size = self.data.len();
+        na::Vector3::new(grid_coord(col, size), self.data[row][col], grid_coord(row, size))
+    }
+
+    // Nearest grid point on x/z
+    pub fn point_to_cell(&self, point: &na::Point3<f32>) -> (usize, usize) {
+        let size = self.data.len();
+        (grid_index(point.z, size), grid_index(point.x, size))
+    }
+
     fn get_user_grid(res: &Resources, grid_path: &str) -> Result<Vec<na::Vector3<f32>>, failure::Error> {
         let grid_file = res.load_cstring(grid_path).map_err(err_msg)?;
         let grid_str = grid_str2file(grid_file, grid_path)?;
@@ -76,18 +202,14 @@ impl Grid {
 
     // Makes isomorphic size*size 2d grid on [-1;1] through input points (poles)
     fn make_grid(size: usize, poles: &Vec<na::Vector3<f32>>, griding_algo: GridingAlgo) -> Vec<Vec<f32>> {
-        let step: f32 = 2. / size as f32;
         let griding_function = Grid::match_griding_function(griding_algo);
-        let mut cur_point: na::Vector3<f32> = na::Vector3::new(-1. - step, 0., -1. - step);
         let mut grid: Vec<Vec<f32>> = vec![vec![0.; size]; size];
 
-        for row in &mut grid {
-            cur_point.z += step;
-            for elem in row {
-                cur_point.x += step;
+        for (i, row) in grid.iter_mut().enumerate() {
+            for (j, elem) in row.iter_mut().enumerate() {
+                let cur_point = na::Vector3::new(grid_coord(j, size), 0., grid_coord(i, size));
                 *elem = griding_function(&cur_point, poles);
             }
-            cur_point.x = -1. - step;
         }
         grid
     }
@@ -142,6 +264,16 @@ impl Grid {
     }
 }
 
+// x or z in [-1;1] where grid point `idx` of a `size` points side is sampled, the surface mesh
+// puts its vertices at the same places
+fn grid_coord(idx: usize, size: usize) -> f32 {
+    -1. + idx as f32 * 2. / (size - 1) as f32
+}
+
+fn grid_index(coord: f32, size: usize) -> usize {
+    (((coord + 1.) * (size - 1) as f32 / 2.).round() as usize).min(size - 1)
+}
+
 fn max(a: f32, b: f32) -> f32 {
     if a > b {
         a
@@ -155,6 +287,17 @@ fn length_on_xz(p1: &na::Vector3<f32>, p2: &na::Vector3<f32>) -> f32 {
     ((p1.x - p2.x).powf(2.) + (p1.z - p2.z).powf(2.)).sqrt()
 }
 
+fn clamp_pole(pole: na::Vector3<f32>) -> na::Vector3<f32> {
+    na::Vector3::new(pole.x.clamp(-1., 1.), pole.y.clamp(0., 1.), pole.z.clamp(-1., 1.))
+}
+
+fn poles_to_mod1(poles: &[na::Vector3<f32>]) -> String {
+    poles.iter()
+        .map(|pole| format!("{:.4},{:.4},{:.4}", pole.x, pole.y, pole.z))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
 fn grid_str2file(str: CString, filename: &str) -> Result<String, Error> {
     str.into_string().map_err(
         |_| Error::UnableConvertFileToString { name: filename.into() }
@@ -0,0 +1,123 @@
+use std::time::Instant;
+use gl_render::Viewport;
+use gl_render::text::Text;
+use resources::Resources;
+use crate::game_data::GameData;
+use crate::game_data::controls::MouseMode;
+
+const FONT: &str = "fonts/DejaVuSansMono.ttf";
+const FONT_SIZE: f32 = 14.;
+const FPS_PERIOD: f32 = 0.5;    // seconds between fps updates
+const MARGIN: f32 = 10.;
+const TOOLTIP_OFFSET: f32 = 16.;   // from cursor to the tooltip corner
+const TEXT_COLOR: (f32, f32, f32, f32) = (1., 1., 1., 1.);
+const BACKGROUND_COLOR: (f32, f32, f32, f32) = (0., 0., 0., 0.5);
+
+const KEY_HELP: &str = "\
+Esc exit            F10 HUD, Tab this help
+Q water level up    W A S D waves     R rain    F flush
+1 RBF  2 Kriging    T mouse mode: camera, sculpt, add water, inspect, poles
+Z X C V brush       [ ] brush radius  E export  P save poles
+Up Down pole height Delete remove pole
+LMB orbit  RMB pan  wheel zoom        I K J L O U fly
+H reset camera      N projection      B bookmark  F1..F9 go to  G play path
+, . ' ; sun         / specular        M water view
+3 4 render modes    5 palette  6 contours  7 legend  8 water colour  9 panel
+0 section           Y section axis    Home End move section  = section energy
+- top-down map      \\ compare griding  ` difference map
+F12 screenshot      F11 record";
+
+// Text overlay with simulation state and key help
+pub struct Hud {
+    pub visible: bool,
+    pub help: bool,
+    text: Text,
+    fps: f32,
+    frames: usize,
+    since: Instant,
+}
+
+impl Hud {
+    pub fn new(res: &Resources, gl: &gl::Gl, visible: bool) -> Result<Hud, failure::Error> {
+        let text = Text::new(gl, res, "shaders/text", FONT, FONT_SIZE)?;
+        Ok(Hud { visible, help: false, text, fps: 0., frames: 0, since: Instant::now() })
+    }
+
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<bool, gl_render::Error> {
+        self.text.reload_shaders(res)
+    }
+
+    pub fn get_text(&self) -> &Text {
+        &self.text
+    }
+
+    // Called once per rendered frame
+    pub fn tick(&mut self) {
+        self.frames += 1;
+        let elapsed = self.since.elapsed().as_secs_f32();
+        if elapsed >= FPS_PERIOD {
+            self.fps = self.frames as f32 / elapsed;
+            self.frames = 0;
+            self.since = Instant::now();
+        }
+    }
+
+    // Pixel row under the status box
+    pub fn bottom(&self, status: &str) -> f32 {
+        MARGIN + self.text.measure(status).1
+    }
+
+    pub fn render(&self, viewport: &Viewport, status: &str) {
+        if !self.visible {
+            return ;
+        }
+        self.text.render(viewport, status, MARGIN, MARGIN, TEXT_COLOR, Some(BACKGROUND_COLOR));
+        if self.help {
+            let (_, height) = self.text.measure(KEY_HELP);
+            let y = viewport.h as f32 - height - MARGIN;
+            self.text.render(viewport, KEY_HELP, MARGIN, y, TEXT_COLOR, Some(BACKGROUND_COLOR));
+        }
+    }
+
+    // Below and to the right of the cursor, moved back into the window near its edges
+    pub fn render_tooltip(&self, viewport: &Viewport, label: &str, cursor: na::Vector2<i32>) {
+        let (width, height) = self.text.measure(label);
+        let x = (cursor.x as f32 + TOOLTIP_OFFSET).min(viewport.w as f32 - width).max(0.);
+        let y = (cursor.y as f32 + TOOLTIP_OFFSET).min(viewport.h as f32 - height).max(0.);
+        self.text.render(viewport, label, x, y, TEXT_COLOR, Some(BACKGROUND_COLOR));
+    }
+}
+
+impl GameData {
+    pub fn hud_status(&self) -> String {
+        let (level, level_max) = self.water.get_water_level();
+        let mut status = format!(
+            "FPS: {:.1}   step: {}\n\
+             Griding: {}\n\
+             Rain: {}   water level: {} / {}   particles: {}\n\
+             Mouse: {:?}   water: {:?} by {:?}",
+            self.hud.fps, self.step,
+            match &self.comparison {
+                Some(comparison) => format!("{:?} | {:?}", self.grid.get_griding_algo(), comparison.grid.get_griding_algo()),
+                None => format!("{:?}", self.grid.get_griding_algo()),
+            },
+            if self.controls.is_rain { "on" } else { "off" }, level, level_max, self.water.get_particle_count(),
+            self.controls.mouse_mode, self.water.view, self.water.color());
+        if self.recorder.is_recording() {
+            status += &format!("\nRecording: {} frames", self.recorder.get_frames());
+        }
+        if !self.hud.help {
+            status += "\nTab: key help";
+        }
+        status
+    }
+
+    // Cell under cursor while inspecting
+    pub fn inspect_label(&self) -> Option<String> {
+        if self.controls.mouse_mode != MouseMode::Inspect {
+            return None;
+        }
+        let info = self.pick_cell()?;
+        Some(format!("x: {}, z: {}\nheight: {:.3}\nwater depth: {}", info.cell.x, info.cell.z, info.height, info.depth))
+    }
+}
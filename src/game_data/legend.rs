@@ -0,0 +1,118 @@
+use crate::gl_render::{self, buffer, data};
+use crate::resources::Resources;
+use crate::game_data::blocks;
+use gl_render::text::Text;
+use gl_render::uniform;
+
+// Bar placement in normalized device coordinates
+const LEGEND_LEFT: f32 = 0.88;
+const LEGEND_RIGHT: f32 = 0.94;
+const LEGEND_BOTTOM: f32 = -0.8;
+const LEGEND_TOP: f32 = 0.8;
+const LABEL_STEP_MIN: f32 = 0.1;    // of height, labels closer than that get too crowded
+const LABEL_GAP: f32 = 6.;          // in pixels between label and bar
+const LABEL_COLOR: (f32, f32, f32, f32) = (1., 1., 1., 1.);
+const LABEL_BACKGROUND: (f32, f32, f32, f32) = (0., 0., 0., 0.5);
+
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = 0]
+    pos: data::f32_f32_f32_f32,     // (x, y, u, height)
+}
+
+impl From<(f32, f32, f32, f32)> for Vertex {
+    fn from(elem: (f32, f32, f32, f32)) -> Self {
+        Vertex { pos: elem.into() }
+    }
+}
+
+// Vertical colour bar from height 0 (bottom) to 1 (top) with ticks, drawn over the scene
+pub struct Legend {
+    pub visible: bool,
+    program: gl_render::Program,
+    _vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+}
+
+impl Legend {
+    pub fn new(res: &Resources, gl: &gl::Gl, visible: bool) -> Result<Legend, failure::Error> {
+        let program = gl_render::Program::from_res(gl, res, "shaders/legend")?;
+        blocks::bind(&program);
+
+        let vertices: Vec<Vertex> = vec![
+            (LEGEND_LEFT, LEGEND_BOTTOM, 0., 0.).into(),
+            (LEGEND_RIGHT, LEGEND_BOTTOM, 1., 0.).into(),
+            (LEGEND_LEFT, LEGEND_TOP, 0., 1.).into(),
+            (LEGEND_RIGHT, LEGEND_TOP, 1., 1.).into(),
+        ];
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vertices);
+        vbo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        Ok(Legend { visible, program, _vbo: vbo, vao })
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Tick interval is set again by GameData::apply_color_map
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<bool, gl_render::Error> {
+        let reloaded = self.program.reload_if_changed(res)?;
+        if reloaded {
+            blocks::bind(&self.program);
+        }
+        Ok(reloaded)
+    }
+
+    pub fn render(&self, gl: &gl::Gl) {
+        if !self.visible {
+            return ;
+        }
+        self.program.use_it();
+        self.vao.bind();
+
+        unsafe {
+            gl.Disable(gl::DEPTH_TEST);
+            gl.DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            gl.Enable(gl::DEPTH_TEST);
+        }
+        self.vao.unbind();
+    }
+
+    // Height values to the left of the bar at multiples of tick interval
+    pub fn render_labels(&self, text: &Text, viewport: &gl_render::Viewport, tick_interval: f32) {
+        if !self.visible || tick_interval <= 0. {
+            return ;
+        }
+        let step = tick_interval * (LABEL_STEP_MIN / tick_interval).ceil().max(1.);
+        let left = (LEGEND_LEFT + 1.) / 2. * viewport.w as f32 - LABEL_GAP;
+        let labels = (1. / step + 1e-3).floor() as usize;
+        for i in 0..=labels {
+            let height = i as f32 * step;
+            let label = format!("{:.2}", height);
+            let (width, line_height) = text.measure(&label);
+            let y_ndc = LEGEND_BOTTOM + height * (LEGEND_TOP - LEGEND_BOTTOM);
+            let y = (1. - y_ndc) / 2. * viewport.h as f32 - line_height / 2.;
+            text.render(viewport, &label, left - width, y, LABEL_COLOR, Some(LABEL_BACKGROUND));
+        }
+    }
+}
+
+impl uniform::HasUniform<f32> for Legend {
+    fn apply_uniform(&self, _gl: &gl::Gl, data: &f32, name: &str) -> Result<(), failure::Error> {
+        self.program.set_uniform(name, data);
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+const ANGLE_STEP: f32 = PI / 12.;
+
+// Light uniform block
+#[derive(Std140)]
+pub struct LightBlock {
+    direction: na::Vector3<f32>,
+    ambient: f32,
+    specular: bool,
+}
+
+// Directional sun with ambient term, angles are in radians
+pub struct Light {
+    pub azimuth: f32,
+    pub elevation: f32,
+    pub ambient: f32,
+    pub specular: bool,
+}
+
+impl Light {
+    pub fn new(azimuth: f32, elevation: f32) -> Light {
+        Light {
+            azimuth: azimuth % (2. * PI),
+            elevation: elevation.clamp(0., FRAC_PI_2),
+            ambient: 0.3,
+            specular: false,
+        }
+    }
+
+    // Unit vector pointing towards the sun
+    pub fn direction(&self) -> na::Vector3<f32> {
+        na::Vector3::new(
+            self.elevation.cos() * self.azimuth.cos(),
+            self.elevation.sin(),
+            self.elevation.cos() * self.azimuth.sin())
+    }
+
+    pub fn block(&self) -> LightBlock {
+        LightBlock { direction: self.direction(), ambient: self.ambient, specular: self.specular }
+    }
+
+    pub fn rotate_azimuth(&mut self, steps: f32) {
+        self.azimuth = (self.azimuth + ANGLE_STEP * steps) % (2. * PI);
+    }
+
+    pub fn rotate_elevation(&mut self, steps: f32) {
+        self.elevation = (self.elevation + ANGLE_STEP * steps).clamp(0., FRAC_PI_2);
+    }
+}
@@ -0,0 +1,227 @@
+use std::str::FromStr;
+use gl_render::{self, buffer, data, Viewport};
+use gl_render::text::Text;
+use gl_render::texture::{self, Texture2D, TextureFilter, TextureFormat};
+use resources::Resources;
+use crate::game_data::GameData;
+use crate::game_data::blocks;
+use crate::game_data::water::Water;
+
+const CELLS_UNIT: gl::types::GLuint = 0;
+const GRID_STEP: usize = 25;        // columns between coordinate lines
+const DEPTH_FULL: f32 = 0.25;       // of grid height, deeper water is drawn with the same colour
+const INSET_SIZE: f32 = 0.3;        // of the smaller window side
+const LEGEND_SPACE: i32 = 110;      // pixels kept for the legend and its labels at the right
+const MARGIN: i32 = 10;
+const LABEL_COLOR: (f32, f32, f32, f32) = (1., 1., 1., 1.);
+const LABEL_BACKGROUND: (f32, f32, f32, f32) = (0., 0., 0., 0.5);
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum MapLayout {
+    Hidden,
+    Inset,      // minimap at the top right corner of the scene
+    Full,       // map instead of the 3d scene
+}
+
+impl MapLayout {
+    pub fn next(self) -> MapLayout {
+        match self {
+            MapLayout::Hidden => MapLayout::Inset,
+            MapLayout::Inset => MapLayout::Full,
+            MapLayout::Full => MapLayout::Hidden,
+        }
+    }
+}
+
+impl FromStr for MapLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hidden" => Ok(MapLayout::Hidden),
+            "inset" => Ok(MapLayout::Inset),
+            "full" => Ok(MapLayout::Full),
+            _ => Err(format!("unknown map layout {}, expected hidden, inset or full", s)),
+        }
+    }
+}
+
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = 0]
+    pos: data::f32_f32_f32_f32,     // (x, y) in NDC, (u, v) on the map
+}
+
+impl From<(f32, f32, f32, f32)> for Vertex {
+    fn from(elem: (f32, f32, f32, f32)) -> Self {
+        Vertex { pos: elem.into() }
+    }
+}
+
+// Top-down orthographic raster of terrain height and water depth per water grid column,
+// x goes to the right and z goes down
+pub struct Map {
+    pub layout: MapLayout,
+    gl: gl::Gl,
+    program: gl_render::Program,
+    texture: Texture2D,
+    _vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+}
+
+impl Map {
+    pub fn new(res: &Resources, gl: &gl::Gl, layout: MapLayout) -> Result<Map, failure::Error> {
+        let program = gl_render::Program::from_res(gl, res, "shaders/map")?;
+
+        let vertices: Vec<Vertex> = vec![
+            (-1., -1., 0., 1.).into(),
+            (1., -1., 1., 1.).into(),
+            (-1., 1., 0., 0.).into(),
+            (1., 1., 1., 0.).into(),
+        ];
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vertices);
+        vbo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        let texture = Texture2D::new(gl, TextureFormat::RG32F, TextureFilter::Nearest);
+        setup_program(&program);
+
+        Ok(Map { layout, gl: gl.clone(), program, texture, _vbo: vbo, vao })
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.layout != MapLayout::Hidden
+    }
+
+    // Square the map is drawn into, in window coordinates with origin at the bottom left
+    fn rect(&self, scene: &Viewport, legend: bool) -> Viewport {
+        let (side, right) = match self.layout {
+            MapLayout::Full => (scene.w.min(scene.h) - 2 * MARGIN, (scene.w + scene.w.min(scene.h)) / 2 - MARGIN),
+            _ => ((scene.w.min(scene.h) as f32 * INSET_SIZE) as i32,
+                  scene.w - MARGIN - if legend { LEGEND_SPACE } else { 0 }),
+        };
+        let top = match self.layout {
+            MapLayout::Full => (scene.h + side) / 2,
+            _ => scene.h - MARGIN,
+        };
+        let mut rect = Viewport::for_window(side, side);
+        rect.x = scene.x + right - side;
+        rect.y = scene.y + top - side;
+        rect
+    }
+
+    // (x, z) column under a window pixel
+    pub fn column_at(&self, window: &Viewport, scene: &Viewport, legend: bool, columns: usize,
+                     mouse: na::Vector2<i32>) -> Option<(usize, usize)> {
+        if !self.is_visible() {
+            return None;
+        }
+        let rect = self.rect(scene, legend);
+        let (left, top) = (rect.x, window.h - rect.y - rect.h);
+        let (u, v) = ((mouse.x - left) as f32 / rect.w as f32, (mouse.y - top) as f32 / rect.h as f32);
+        match (0. ..1.).contains(&u) && (0. ..1.).contains(&v) {
+            true => Some(((u * columns as f32) as usize, (v * columns as f32) as usize)),
+            false => None,
+        }
+    }
+
+    // Relinks the program when its shaders change
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<bool, gl_render::Error> {
+        let reloaded = self.program.reload_if_changed(res)?;
+        if reloaded {
+            setup_program(&self.program);
+        }
+        Ok(reloaded)
+    }
+
+    // Leaves the whole window as viewport
+    pub fn render(&self, window: &Viewport, scene: &Viewport, legend: bool, water: &Water, cursor: Option<(usize, usize)>)
+                  -> Result<(), texture::Error> {
+        if !self.is_visible() {
+            return Ok(());
+        }
+        let (columns, cells) = water.map();
+        self.texture.upload(columns as i32, columns as i32, &cells)?;
+
+        self.program.use_it();
+        self.program.set_uniform("cursor", &cursor.map_or((-1, -1), |(x, z)| (x as i32, z as i32)));
+        self.rect(scene, legend).use_it(&self.gl);
+        self.texture.bind(CELLS_UNIT);
+        unsafe {
+            self.gl.Disable(gl::DEPTH_TEST);
+            self.vao.bind();
+            self.gl.DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            self.vao.unbind();
+            self.gl.Enable(gl::DEPTH_TEST);
+        }
+        self.texture.unbind(CELLS_UNIT);
+        window.use_it(&self.gl);
+        Ok(())
+    }
+
+    // Column numbers along the top and left edges and the state of the cursor column under the map
+    pub fn render_labels(&self, text: &Text, window: &Viewport, scene: &Viewport, legend: bool, water: &Water,
+                         cursor: Option<(usize, usize)>) {
+        if !self.is_visible() {
+            return ;
+        }
+        let (columns, _) = water.section_size();
+        let rect = self.rect(scene, legend);
+        let (left, top) = (rect.x as f32, (window.h - rect.y - rect.h) as f32);
+        let scale = rect.w as f32 / columns as f32;
+        for column in (GRID_STEP..columns).step_by(GRID_STEP) {
+            let label = column.to_string();
+            let (width, height) = text.measure(&label);
+            let shift = column as f32 * scale;
+            text.render(window, &label, left + shift - width / 2., top + 2., LABEL_COLOR, Some(LABEL_BACKGROUND));
+            text.render(window, &label, left + 2., top + shift - height / 2., LABEL_COLOR, Some(LABEL_BACKGROUND));
+        }
+        let info = match cursor {
+            Some((x, z)) => {
+                let (ground, depth) = water.column(x, z);
+                format!("x {}  z {}  ground {:.2}  water {:.2}", x, z, ground, depth)
+            },
+            None => "x right, z down".to_owned(),
+        };
+        text.render(window, &info, left, top + rect.h as f32 + 8., LABEL_COLOR, Some(LABEL_BACKGROUND));
+    }
+}
+
+// Block bindings and uniforms which do not change, set again whenever the program is relinked
+fn setup_program(program: &gl_render::Program) {
+    blocks::bind(program);
+    program.set_uniform("cells", &(CELLS_UNIT as i32));
+    program.set_uniform("grid_step", &(GRID_STEP as i32));
+    program.set_uniform("depth_full", &DEPTH_FULL);
+}
+
+impl GameData {
+    // Column under the cursor: read off the map when the cursor is over it, otherwise picked in 3d
+    pub fn map_cursor(&self) -> Option<(usize, usize)> {
+        let scene = self.scene_viewport();
+        let (columns, _) = self.water.section_size();
+        let over_map = self.map.column_at(&self.viewport, &scene, self.map_legend(), columns,
+                                          self.controls.get_mouse_pos());
+        if over_map.is_some() || self.map.layout == MapLayout::Full {
+            return over_map;
+        }
+        self.pick_surface().map(|(row, col)| (col.min(columns - 1), row.min(columns - 1)))
+    }
+
+    // Whether the legend shares the corner with the inset, it moves to the compared half
+    pub fn map_legend(&self) -> bool {
+        self.legend.visible && self.comparison.is_none()
+    }
+}
@@ -4,31 +4,112 @@ use gl_render::uniform::HasUniform;
 use resources::Resources;
 use surface::Surface;
 use crate::camera::MVP;
+use crate::camera_path::CameraPath;
 use controls::{Controls};
 use grid::{Grid, GridingAlgo};
-use water::{Water};
+use water::{Water, WaterColor};
+use brush::Brush;
+use poles::Poles;
+use light::Light;
+use render_mode::RenderMode;
+use color_map::ColorMap;
+use legend::Legend;
+use hud::Hud;
+use panel::Panel;
+use section::{Section, SectionLayout, SectionAxis};
+use map::{Map, MapLayout};
+use comparison::Comparison;
+use blocks::Blocks;
+use crate::config::Config;
+use crate::capture::{Offscreen, Recorder, RecordFormat};
+use controls::MouseMode;
+use shader_reload::ShaderWatch;
 
 pub mod controls;
 mod surface;
 mod water;
 mod grid;
+mod brush;
+mod picking;
+mod poles;
+mod light;
+mod render_mode;
+mod color_map;
+mod legend;
+mod hud;
+mod panel;
+mod section;
+mod map;
+mod comparison;
+mod blocks;
+mod shader_reload;
 
 pub struct GameData {
     gl: gl::Gl,
+    res: Resources,
     viewport: Viewport,
     grid: Grid,
     surface: Surface,
     water: Water,
+    poles: Poles,
     mvp: MVP,
+    blocks: Blocks,
+    shader_watch: Option<ShaderWatch>,
+    camera_path: CameraPath,
+    brush: Brush,
+    light: Light,
+    surface_mode: RenderMode,
+    water_mode: RenderMode,
+    color_map: ColorMap,
+    contour_interval: f32,
+    contours: bool,
+    legend: Legend,
+    hud: Hud,
+    panel: Panel,
+    section: Section,
+    map: Map,
+    comparison: Option<Comparison>,
     color_buffer: ColorBuffer,
+    offscreen: Offscreen,
+    recorder: Recorder,
+    screenshot: bool,
+    headless: bool,
+    step: usize,
     pub controls: Controls,
     need_exit: bool,
 }
 
 pub const GRID_WIDTH: usize = 200;
+const LEGEND_TICK_INTERVAL: f32 = 0.1;
 
 impl GameData {
     pub fn new(gl: &gl::Gl, res: &Resources, grid_path: &str) -> Result<GameData, failure::Error> {
+        let config = Config::load(res, "config.cfg")?;
+        let surface_mode = config.get_or("surface_render_mode", RenderMode::Filled)?;
+        let water_mode = config.get_or("water_render_mode", RenderMode::Filled)?;
+        let color_map = config.get_or("terrain_palette", ColorMap::classic())?;
+        let contour_interval = config.get_or("contour_interval", 0.05f32)?.max(0.);
+        let contours = config.get_or("show_contours", false)?;
+        let show_legend = config.get_or("show_legend", true)?;
+        let show_hud = config.get_or("show_hud", true)?;
+        let show_panel = config.get_or("show_panel", false)?;
+        let section_layout = config.get_or("section", SectionLayout::Hidden)?;
+        let section_axis = config.get_or("section_axis", SectionAxis::X)?;
+        let section_energy = config.get_or("section_energy", false)?;
+        let map_layout = config.get_or("map", MapLayout::Hidden)?;
+        let water_color = config.get_or("water_color", WaterColor::Depth)?;
+        let sun_azimuth = config.get_or("sun_azimuth", 45f32)?.to_radians();
+        let sun_elevation = config.get_or("sun_elevation", 45f32)?.to_radians();
+        let shader_watch = match config.get_or("reload_shaders", true)? {
+            true => Some(ShaderWatch::new(res)),
+            false => None,
+        };
+        let recorder = Recorder::new(
+            &config.get_or("capture_dir", "captures".to_owned())?,
+            config.get_or("record_format", RecordFormat::Png)?,
+            config.get_or("record_every", 1)?,
+            config.get_or("gif_delay", 4)?);
+
         let color_buffer: gl_render::ColorBuffer = (0.3, 0.3, 0.5).into(); // TODO add to config
         color_buffer.use_it(&gl);
 
@@ -37,62 +118,221 @@ impl GameData {
 
         let grid = Grid::new(&res, grid_path, GRID_WIDTH, GridingAlgo::RadialBasisFunction)?;
         let surface = Surface::new(&res, &gl, grid.get_data())?;
-        let water = Water::new(&res, &gl, grid.get_data())?;
+        let mut water = Water::new(res, gl, grid.get_data())?;
+        water.set_color(water_color);
+        let poles = Poles::new(res, gl, grid.get_poles())?;
 
         let mvp = MVP::new();
-        surface.apply_uniform(&gl, &mvp, "mvp_transform").map_err(err_msg)?;
-        water.apply_uniform(&gl, &mvp, "mvp_transform").map_err(err_msg)?;
+        let blocks = Blocks::new(gl);
+        let camera_path = CameraPath::new(&source_resources(res), grid_path)?;
 
+        let brush = Brush::new();
+        let light = Light::new(sun_azimuth, sun_elevation);
+        let legend = Legend::new(res, gl, show_legend)?;
+        let hud = Hud::new(res, gl, show_hud)?;
+        let panel = Panel::new(show_panel);
+        let section = Section::new(res, gl, section_layout, section_axis, water.section_size().0 / 2, section_energy)?;
+        let map = Map::new(res, gl, map_layout)?;
+        let offscreen = Offscreen::new(gl, viewport.w, viewport.h)?;
         let controls = Controls::new();
         let need_exit = false;
 
-        Ok(GameData { gl: gl.clone(), viewport, surface, mvp, color_buffer, controls, grid, water, need_exit })
+        let mut game_data = GameData { gl: gl.clone(), res: res.clone(), viewport, surface, poles, mvp, blocks, shader_watch, camera_path, brush, light, surface_mode, water_mode,
+            color_map, contour_interval, contours, legend, hud, panel, section, map, comparison: None, color_buffer, offscreen, recorder, screenshot: false, headless: false, step: 0,
+            controls, grid, water, need_exit };
+        game_data.apply_color_map()?;
+        game_data.update_projection()?;
+        Ok(game_data)
     }
 
     pub fn resized(&mut self, w: i32, h: i32) -> Result<(), failure::Error> {
         self.viewport.update_size(w, h);
         self.viewport.use_it(&self.gl);
-        self.mvp.projection_recalc(w, h);
-        self.apply_uniforms().map_err(err_msg)?;
+        self.update_projection()?;
+        if self.recorder.is_recording() {
+            println!("Window resized, recording stopped after {} frames", self.recorder.stop().unwrap_or(0));
+        }
+        self.offscreen.resize(w, h)?;
         Ok(())
     }
 
     pub fn modulate(&mut self) -> Result<(), failure::Error> {
-        if self.controls.is_rain {
-            self.water.add_rain_particles();
+        let rain = self.controls.is_rain;
+        self.for_each_water(|water| {
+            if rain {
+                water.add_rain_particles();
+            }
+            water.modulate();
+        });
+        self.step += 1;
+        if let Some(pose) = self.camera_path.step() {
+            self.mvp.set_pose(&pose);
         }
-        self.water.modulate();
         self.apply_uniforms().map_err(err_msg)
     }
 
-    pub fn render(&self) {
-        self.color_buffer.clear(&self.gl);
-        self.surface.render(&self.gl, gl::TRIANGLES); // TODO: add key for changing render mode
-        self.water.render(&self.gl, gl::TRIANGLES);
+    // Scene goes through the offscreen framebuffer when the frame has to be saved
+    pub fn render(&mut self) -> Result<(), failure::Error> {
+        self.reload_shaders();
+        self.hud.tick();
+        let record = self.recorder.need_frame(self.step);
+        if !record && !self.screenshot {
+            if !self.headless {
+                self.draw()?;
+            }
+            return Ok(());
+        }
+
+        self.offscreen.bind();
+        self.draw()?;
+        self.offscreen.unbind();
+        let (width, height) = (self.offscreen.width, self.offscreen.height);
+        let mut pixels = self.offscreen.read_pixels()?;
+        if self.screenshot {
+            self.screenshot = false;
+            println!("Screenshot saved to {}", self.recorder.screenshot(&pixels, width, height)?);
+        }
+        if record {
+            self.recorder.add_frame(&mut pixels, width, height)?;
+        }
+        if !self.headless {
+            self.offscreen.blit_to_screen();
+        }
+        Ok(())
+    }
 
-        // TODO: depth buffer
+    // Records frames without a visible window until `frames` are saved, flies through camera bookmarks if any
+    pub fn start_headless(&mut self, frames: usize) -> Result<(), failure::Error> {
+        self.headless = true;
+        self.camera_path.play();
+        println!("Recording {} frames to {}", frames, self.recorder.start(self.viewport.w, self.viewport.h)?);
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) {
+        if let Some(frames) = self.recorder.stop() {
+            println!("Recording stopped after {} frames", frames);
+        }
+    }
+
+    pub fn get_recorded_frames(&self) -> usize {
+        self.recorder.get_frames()
+    }
+
+    // Opaque objects go first, then transparent water over them
+    fn draw(&self) -> Result<(), failure::Error> {
+        self.color_buffer.clear(&self.gl);
         unsafe {
             self.gl.Clear(gl::DEPTH_BUFFER_BIT);
         }
+        let scene = self.scene_viewport();
+        scene.use_it(&self.gl);
+        if self.map.layout != MapLayout::Full {
+            self.surface_mode.render(&self.gl, |mode| self.surface.render(&self.gl, mode));
+            self.water_mode.render(&self.gl, |mode| self.water.render(&self.gl, mode));
+            if self.controls.mouse_mode == MouseMode::Poles {
+                self.poles.render(&self.gl);
+            }
+        }
+        let compared = self.comparison.as_ref().map(|comparison| {
+            let right = comparison::split(&self.section.scene_viewport(&self.viewport)).1;
+            right.use_it(&self.gl);
+            comparison.render(&self.gl, self.surface_mode, self.water_mode);
+            right
+        });
+        let legend_viewport = compared.as_ref().unwrap_or(&scene);
+        self.legend.render(&self.gl);
+        self.legend.render_labels(self.hud.get_text(), legend_viewport, self.tick_interval());
+        self.viewport.use_it(&self.gl);
+        if let (Some(comparison), Some(right)) = (&self.comparison, &compared) {
+            comparison.render_overlay(self.hud.get_text(), &self.viewport, &scene, right, &self.grid)?;
+        }
+        let cursor = match self.map.is_visible() {
+            true => self.map_cursor(),
+            false => None,
+        };
+        self.map.render(&self.viewport, &scene, self.map_legend(), &self.water, cursor)?;
+        self.map.render_labels(self.hud.get_text(), &self.viewport, &scene, self.map_legend(), &self.water, cursor);
+        self.section.render(&self.viewport, &self.water)?;
+        self.section.render_label(self.hud.get_text(), &self.viewport, &self.water);
+        self.hud.render(&self.viewport, &self.hud_status());
+        self.panel.render(self.hud.get_text(), &self.viewport);
+        if let Some(label) = self.inspect_label() {
+            self.hud.render_tooltip(&self.viewport, &label, self.controls.get_mouse_pos());
+        }
+        Ok(())
     }
 
     pub fn need_exit(&self) -> bool {
         self.need_exit
     }
 
+    // Camera and light go to the shared uniform blocks once per frame
     fn apply_uniforms(&self) -> Result<(), failure::Error> {
-        self.surface.apply_uniform(&self.gl, &self.mvp, "mvp_transform").map_err(err_msg)?;
-        self.water.apply_uniform(&self.gl, &self.mvp, "mvp_transform").map_err(err_msg)?;
+        self.blocks.camera.update(&self.mvp.block());
+        self.blocks.light.update(&self.light.block());
         Ok(())
     }
 
+    // Part of the window the main scene is drawn into, the compared one takes the right half of it
+    fn scene_viewport(&self) -> Viewport {
+        let scene = self.section.scene_viewport(&self.viewport);
+        match self.comparison {
+            Some(_) => comparison::split(&scene).0,
+            None => scene,
+        }
+    }
+
+    // Projection follows the part of the window the scene is drawn into
+    fn update_projection(&mut self) -> Result<(), failure::Error> {
+        let scene = self.scene_viewport();
+        self.mvp.projection_recalc(scene.w, scene.h);
+        self.apply_uniforms()
+    }
+
+    // Colour map and contours change only on user actions, so they are not a part of per frame uniforms
+    fn apply_color_map(&self) -> Result<(), failure::Error> {
+        let contour_interval = match self.contours {
+            true => self.contour_interval,
+            false => 0.,
+        };
+        let tick_interval = self.tick_interval();
+        self.blocks.color_map.update(&self.color_map.block());
+        self.surface.apply_uniform(&self.gl, &contour_interval, "contour_interval").map_err(err_msg)?;
+        if let Some(comparison) = &self.comparison {
+            comparison.surface.apply_uniform(&self.gl, &contour_interval, "contour_interval").map_err(err_msg)?;
+        }
+        self.legend.apply_uniform(&self.gl, &tick_interval, "tick_interval").map_err(err_msg)?;
+        Ok(())
+    }
+
+    // Legend ticks follow contour lines when they are on
+    fn tick_interval(&self) -> f32 {
+        match self.contours && self.contour_interval > 0. {
+            true => self.contour_interval,
+            false => LEGEND_TICK_INTERVAL,
+        }
+    }
+
     pub fn init(&self) {
         unsafe {
-            // TODO: depth buffer
             self.gl.Enable(gl::DEPTH_TEST);
             self.gl.DepthFunc(gl::LEQUAL);
             self.gl.DepthRange(0., 1.);
             self.gl.ClearDepth(1.);
+            self.gl.Enable(gl::PROGRAM_POINT_SIZE);
+            self.gl.Enable(gl::BLEND);
+            self.gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
         }
     }
 }
+
+// Assets next to the executable are copies made by build.rs and are overwritten by the next build,
+// files meant to be edited or kept go to the source tree when it is still there
+fn source_resources(res: &Resources) -> Resources {
+    let source_assets = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+    match source_assets.is_dir() {
+        true => Resources::from_path(&source_assets),
+        false => res.clone(),
+    }
+}
@@ -0,0 +1,286 @@
+use gl_render::Viewport;
+use gl_render::text::Text;
+use crate::game_data::{GameData, GRID_WIDTH};
+use crate::game_data::grid::GridingAlgo;
+
+const WIDTH: f32 = 320.;
+const LABEL_WIDTH: f32 = 170.;  // sliders' track starts after the label
+const MARGIN: f32 = 10.;
+const PADDING: f32 = 6.;
+const ROW_GAP: f32 = 4.;
+const BACKGROUND_COLOR: (f32, f32, f32, f32) = (0., 0., 0., 0.6);
+const TEXT_COLOR: (f32, f32, f32, f32) = (1., 1., 1., 1.);
+const HEADER_COLOR: (f32, f32, f32, f32) = (1., 0.85, 0.4, 1.);
+const WIDGET_COLOR: (f32, f32, f32, f32) = (0.3, 0.3, 0.3, 0.9);
+const HOVER_COLOR: (f32, f32, f32, f32) = (0.4, 0.4, 0.45, 0.9);
+const FILL_COLOR: (f32, f32, f32, f32) = (0.2, 0.5, 0.9, 0.9);
+
+// (left, top, right, bottom) in pixels
+type Rect = (f32, f32, f32, f32);
+
+enum Item {
+    Fill(Rect, (f32, f32, f32, f32)),
+    Label(String, f32, f32, (f32, f32, f32, f32)),
+}
+
+// Immediate mode widgets: layout, hit testing and the draw list are rebuilt every frame,
+// values live in the simulation itself
+pub struct Panel {
+    pub visible: bool,
+    active: Option<usize>,      // slider dragged by mouse
+    released: Option<usize>,    // slider let go since the last frame
+    items: Vec<Item>,
+    rect: Rect,
+}
+
+impl Panel {
+    pub fn new(visible: bool) -> Panel {
+        Panel { visible, active: None, released: None, items: Vec::new(), rect: (0., 0., 0., 0.) }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // click is where the left button was pressed over the panel since the last frame
+    pub fn begin<'a>(&'a mut self, text: &'a Text, left: f32, top: f32, mouse: na::Vector2<i32>,
+                     pressed: bool, click: Option<na::Vector2<i32>>) -> Ui<'a> {
+        self.released = match pressed {
+            true => None,
+            false => self.active.take(),
+        };
+        self.items.clear();
+        Ui {
+            text,
+            panel: self,
+            mouse: (mouse.x as f32, mouse.y as f32),
+            click: click.map(|click| (click.x as f32, click.y as f32)),
+            left,
+            top,
+            y: top,
+            id: 0,
+        }
+    }
+
+    // Area which takes mouse clicks, none when hidden
+    pub fn get_rect(&self) -> Option<(i32, i32, i32, i32)> {
+        match self.visible {
+            true => Some((self.rect.0 as i32, self.rect.1 as i32, self.rect.2 as i32, self.rect.3 as i32)),
+            false => None,
+        }
+    }
+
+    pub fn render(&self, text: &Text, viewport: &Viewport) {
+        if !self.visible {
+            return ;
+        }
+        text.render_rect(viewport, self.rect, BACKGROUND_COLOR);
+        for item in self.items.iter() {
+            match item {
+                Item::Fill(rect, color) => text.render_rect(viewport, *rect, *color),
+                Item::Label(label, x, y, color) => text.render(viewport, label, *x, *y, *color, None),
+            }
+        }
+    }
+}
+
+// Widgets of one frame, each returns whether it changed its value
+pub struct Ui<'a> {
+    text: &'a Text,
+    panel: &'a mut Panel,
+    mouse: (f32, f32),
+    click: Option<(f32, f32)>,
+    left: f32,
+    top: f32,
+    y: f32,
+    id: usize,
+}
+
+impl<'a> Ui<'a> {
+    pub fn header(&mut self, label: &str) {
+        let (rect, _) = self.row();
+        self.label(label, rect.0, rect.1, HEADER_COLOR);
+    }
+
+    pub fn toggle(&mut self, label: &str, value: &mut bool) -> bool {
+        let (rect, _) = self.row();
+        let changed = self.clicked(rect);
+        if changed {
+            *value = !*value;
+        }
+        let size = rect.3 - rect.1;
+        let check = (rect.0 + 2., rect.1 + 2., rect.0 + size - 2., rect.3 - 2.);
+        self.fill(check, if self.hovered(rect) { HOVER_COLOR } else { WIDGET_COLOR });
+        if *value {
+            self.fill((check.0 + 3., check.1 + 3., check.2 - 3., check.3 - 3.), FILL_COLOR);
+        }
+        self.label(label, rect.0 + size + PADDING, rect.1, TEXT_COLOR);
+        changed
+    }
+
+    pub fn button(&mut self, label: &str) -> bool {
+        let (rect, _) = self.row();
+        self.fill(rect, if self.hovered(rect) { HOVER_COLOR } else { WIDGET_COLOR });
+        self.label(label, rect.0 + PADDING, rect.1, TEXT_COLOR);
+        self.clicked(rect)
+    }
+
+    // value is shown with `decimals` digits after the point
+    pub fn slider(&mut self, label: &str, value: &mut f32, min: f32, max: f32, decimals: usize) -> bool {
+        let (rect, id) = self.row();
+        let track = (rect.0 + LABEL_WIDTH, rect.1 + 2., rect.2, rect.3 - 2.);
+        if self.clicked(track) {
+            self.panel.active = Some(id);
+        }
+        let mut changed = false;
+        if self.panel.active == Some(id) {
+            let part = ((self.mouse.0 - track.0) / (track.2 - track.0)).clamp(0., 1.);
+            let dragged = min + part * (max - min);
+            changed = dragged != *value;
+            *value = dragged;
+        }
+        let part = ((*value - min) / (max - min)).clamp(0., 1.);
+        self.fill(track, if self.hovered(track) { HOVER_COLOR } else { WIDGET_COLOR });
+        self.fill((track.0, track.1, track.0 + part * (track.2 - track.0), track.3), FILL_COLOR);
+        self.label(&format!("{} {:.*}", label, decimals, *value), rect.0, rect.1, TEXT_COLOR);
+        changed
+    }
+
+    pub fn slider_int(&mut self, label: &str, value: &mut usize, min: usize, max: usize) -> bool {
+        let mut dragged = *value as f32;
+        self.slider(label, &mut dragged, min as f32, max as f32, 0);
+        let dragged = dragged.round() as usize;
+        let changed = dragged != *value;
+        *value = dragged;
+        changed
+    }
+
+    // Whether the widget laid out last is a slider let go this frame,
+    // for values too costly to apply on every step of dragging
+    pub fn released(&self) -> bool {
+        self.panel.released == Some(self.id)
+    }
+
+    // Background is put under the widgets laid out so far
+    pub fn end(self) {
+        self.panel.rect = (self.left - PADDING, self.top - PADDING,
+                           self.left + WIDTH + PADDING, self.y - ROW_GAP + PADDING);
+    }
+
+    fn row(&mut self) -> (Rect, usize) {
+        let rect = (self.left, self.y, self.left + WIDTH, self.y + self.text.line_height());
+        self.y = rect.3 + ROW_GAP;
+        self.id += 1;
+        (rect, self.id)
+    }
+
+    fn hovered(&self, rect: Rect) -> bool {
+        contains(rect, self.mouse)
+    }
+
+    fn clicked(&self, rect: Rect) -> bool {
+        self.click.is_some_and(|click| contains(rect, click))
+    }
+
+    fn fill(&mut self, rect: Rect, color: (f32, f32, f32, f32)) {
+        self.panel.items.push(Item::Fill(rect, color));
+    }
+
+    fn label(&mut self, label: &str, x: f32, y: f32, color: (f32, f32, f32, f32)) {
+        self.panel.items.push(Item::Label(label.to_owned(), x, y, color));
+    }
+}
+
+fn contains(rect: Rect, point: (f32, f32)) -> bool {
+    point.0 >= rect.0 && point.0 < rect.2 && point.1 >= rect.1 && point.1 < rect.3
+}
+
+impl GameData {
+    // Lays the panel out under the HUD and applies what was changed on it
+    pub fn update_panel(&mut self) -> Result<(), failure::Error> {
+        let click = self.controls.take_panel_click();
+        if !self.panel.visible {
+            self.controls.panel_rect = None;
+            return Ok(());
+        }
+        let top = match self.hud.visible {
+            true => self.hud.bottom(&self.hud_status()),
+            false => 0.,
+        } + MARGIN + PADDING;
+
+        let mut ui = self.panel.begin(self.hud.get_text(), MARGIN + PADDING, top, self.controls.get_mouse_pos(),
+                                      self.controls.panel_capture.into(), click);
+
+        ui.header("Simulation");
+        ui.toggle("Rain", &mut self.controls.is_rain);
+        ui.slider_int("Rain rate", &mut self.water.params.rain_rate, 1, 100);
+        let mut gravity = self.water.params.gravity as usize;
+        if ui.slider_int("Gravity", &mut gravity, 1, 50) {
+            self.water.params.gravity = gravity as i32;
+        }
+        ui.slider("Wave height", &mut self.water.params.wave_height, 0.1, 0.95, 2);
+        let flush = ui.button("Flush water");
+
+        ui.header("Interpolation");
+        ui.slider_int("Edge poles", &mut self.grid.edge_poles, 0, 60);
+        let edge_poles = ui.released();
+        let rbf = ui.button("Re-grid with radial basis");
+        let kriging = ui.button("Re-grid with kriging");
+
+        ui.header("Rendering");
+        let mut lights = ui.slider("Ambient light", &mut self.light.ambient, 0., 1., 2);
+        lights |= ui.toggle("Specular", &mut self.light.specular);
+        let palette = ui.button(&format!("Palette: {}", self.color_map.name));
+        let mut colors = ui.toggle("Contours", &mut self.contours);
+        colors |= ui.slider("Contour interval", &mut self.contour_interval, 0.01, 0.25, 2);
+        ui.toggle("Legend", &mut self.legend.visible);
+        let mut background = ui.slider("Background red", &mut self.color_buffer.r, 0., 1., 2);
+        background |= ui.slider("Background green", &mut self.color_buffer.g, 0., 1., 2);
+        background |= ui.slider("Background blue", &mut self.color_buffer.b, 0., 1., 2);
+
+        let mut compare_edge_poles = false;
+        let mut compare_rbf = false;
+        let mut compare_kriging = false;
+        if let Some(comparison) = self.comparison.as_mut() {
+            ui.header("Comparison, right half");
+            ui.slider_int("Edge poles", &mut comparison.grid.edge_poles, 0, 60);
+            compare_edge_poles = ui.released();
+            compare_rbf = ui.button("Re-grid with radial basis");
+            compare_kriging = ui.button("Re-grid with kriging");
+            ui.toggle("Difference map", &mut comparison.difference);
+        }
+
+        ui.header("Section");
+        let sections = self.water.section_size().0;
+        ui.slider_int("Position", &mut self.section.position, 0, sections - 1);
+        ui.toggle("Water by energy", &mut self.section.energy);
+        ui.end();
+        self.controls.panel_rect = self.panel.get_rect();
+
+        if flush { self.action_flush() };
+        if edge_poles { self.apply_edge_poles()? };
+        if compare_edge_poles { self.apply_comparison_edge_poles()? };
+        if rbf { self.action_set_radial_basis()? };
+        if kriging { self.action_set_kriging()? };
+        if compare_rbf { self.regrid_comparison(GridingAlgo::RadialBasisFunction)? };
+        if compare_kriging { self.regrid_comparison(GridingAlgo::Kriging)? };
+        if palette { self.action_palette()? };
+        if colors { self.apply_color_map()? };
+        if lights { self.apply_uniforms()? };
+        if background { self.color_buffer.use_it(&self.gl) };
+        Ok(())
+    }
+
+    // Terrain is interpolated again with the current algorithm, water stays like after pole edits
+    fn apply_edge_poles(&mut self) -> Result<(), failure::Error> {
+        self.grid.update_grid(GRID_WIDTH, self.grid.get_griding_algo());
+        self.action_poles_changed()
+    }
+
+    fn apply_comparison_edge_poles(&mut self) -> Result<(), failure::Error> {
+        if let Some(comparison) = self.comparison.as_mut() {
+            comparison.set_poles(self.grid.get_poles())?;
+        }
+        Ok(())
+    }
+}
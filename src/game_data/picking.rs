@@ -0,0 +1,81 @@
+use crate::game_data::GameData;
+use crate::game_data::comparison;
+
+// Water grid cell under cursor with the state of its column
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+pub struct CellInfo {
+    pub cell: na::Vector3<usize>,   // (x, y, z) in water grid
+    pub height: f32,                // surface height under the cell
+    pub depth: usize,               // water particles in the column
+}
+
+impl GameData {
+    // Ray from near to far plane through the cursor as (near point, far point - near point)
+    pub fn cursor_ray(&self) -> Option<(na::Point3<f32>, na::Vector3<f32>)> {
+        let mouse = self.scene_cursor();
+        let scene = self.scene_viewport();
+        let ndc = na::Vector2::new(
+            2. * mouse.x as f32 / scene.w as f32 - 1.,
+            1. - 2. * mouse.y as f32 / scene.h as f32);
+        self.mvp.cursor_ray(ndc)
+    }
+
+    // Cursor from the top left corner of the scene, both halves of a comparison share the camera,
+    // so a click on the right one is taken at the same place of the left one
+    pub fn scene_cursor(&self) -> na::Vector2<i32> {
+        let mouse = self.controls.get_mouse_pos();
+        let left = self.scene_viewport().x;
+        let right = match self.comparison {
+            Some(_) => comparison::split(&self.section.scene_viewport(&self.viewport)).1.x,
+            None => i32::MAX,
+        };
+        match mouse.x >= right {
+            true => na::Vector2::new(mouse.x - right, mouse.y),
+            false => na::Vector2::new(mouse.x - left, mouse.y),
+        }
+    }
+
+    // (row, col) of the surface grid point under cursor
+    pub fn pick_surface(&self) -> Option<(usize, usize)> {
+        let (origin, dir) = self.cursor_ray()?;
+        self.grid.ray_cast(&origin, &dir)
+    }
+
+    // First non empty water grid cell under cursor
+    pub fn pick_cell(&self) -> Option<CellInfo> {
+        let (origin, dir) = self.cursor_ray()?;
+        let cell = self.water.ray_cast(&origin, &dir)?;
+        let (row, col) = self.grid.point_to_cell(&self.water.cell_center(&cell));
+        Some(CellInfo {
+            cell,
+            height: self.grid.get_data()[row][col],
+            depth: self.water.column_depth(cell.x, cell.z),
+        })
+    }
+}
+
+// Clips ray to the [-1;1]x[0;1]x[-1;1] box, returns range of t inside of it
+pub fn clip_to_domain(origin: &na::Point3<f32>, dir: &na::Vector3<f32>) -> Option<(f32, f32)> {
+    let bounds = [(-1., 1.), (0., 1.), (-1., 1.)];
+    let mut t_min: f32 = 0.;
+    let mut t_max: f32 = 1.;
+
+    for (axis, (lo, hi)) in bounds.iter().enumerate() {
+        if dir[axis].abs() < f32::EPSILON {
+            if origin[axis] < *lo || origin[axis] > *hi {
+                return None;
+            }
+            continue ;
+        }
+        let t0 = (lo - origin[axis]) / dir[axis];
+        let t1 = (hi - origin[axis]) / dir[axis];
+        t_min = t_min.max(t0.min(t1));
+        t_max = t_max.min(t0.max(t1));
+    }
+
+    match t_min <= t_max {
+        true => Some((t_min, t_max)),
+        false => None,
+    }
+}
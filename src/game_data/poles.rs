@@ -0,0 +1,123 @@
+use crate::gl_render::{self, buffer, data};
+use crate::resources::Resources;
+use crate::camera::MVP;
+use crate::game_data::blocks;
+
+const POLE_COLOR: (f32, f32, f32, f32) = (1., 0.5, 0., 1.);
+const POLE_SELECTED_COLOR: (f32, f32, f32, f32) = (1., 0., 0., 1.);
+const POLE_PICK_RADIUS: f32 = 10.;     // in pixels
+
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = 0]
+    pos: data::f32_f32_f32,
+    #[location = 1]
+    color: data::f32_f32_f32_f32,
+}
+
+impl From<((f32, f32, f32), (f32, f32, f32, f32))> for Vertex {
+    fn from(elem: ((f32, f32, f32), (f32, f32, f32, f32))) -> Self {
+        Vertex { pos: elem.0.into(), color: elem.1.into() }
+    }
+}
+
+// Markers of user input points: a stem from the ground and a dot on top of every pole
+pub struct Poles {
+    pub selected: Option<usize>,
+    pub dragging: bool,
+    program: gl_render::Program,
+    vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+    count: usize,
+}
+
+impl Poles {
+    pub fn new(res: &Resources, gl: &gl::Gl, poles: &[na::Vector3<f32>]) -> Result<Poles, failure::Error> {
+        let program = gl_render::Program::from_res(gl, res, "shaders/poles")?;
+        blocks::bind(&program);
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        let vao = buffer::VertexArray::new(gl);
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        let mut markers = Poles {
+            selected: None,
+            dragging: false,
+            program, vbo, vao,
+            count: 0,
+        };
+        markers.set_poles(poles);
+        Ok(markers)
+    }
+
+    pub fn set_poles(&mut self, poles: &[na::Vector3<f32>]) {
+        let mut stems: Vec<Vertex> = Vec::with_capacity(poles.len() * 2);
+        let mut heads: Vec<Vertex> = Vec::with_capacity(poles.len());
+
+        for (i, pole) in poles.iter().enumerate() {
+            let color = match self.selected {
+                Some(selected) if selected == i => POLE_SELECTED_COLOR,
+                _ => POLE_COLOR,
+            };
+            stems.push(((pole.x, 0., pole.z), color).into());
+            stems.push(((pole.x, pole.y, pole.z), color).into());
+            heads.push(((pole.x, pole.y, pole.z), color).into());
+        }
+        stems.append(&mut heads);
+
+        self.vbo.bind();
+        self.vbo.dynamic_draw_data(&stems);
+        self.vbo.unbind();
+        self.count = poles.len();
+    }
+
+    // Index of the pole which top is within POLE_PICK_RADIUS pixels from cursor,
+    // poles behind the camera are not on the screen
+    pub fn pick(poles: &[na::Vector3<f32>], mvp: &MVP, viewport: &gl_render::Viewport,
+                mouse: na::Vector2<i32>) -> Option<usize> {
+        let transform = mvp.get_transform();
+        poles.iter()
+            .map(|pole| {
+                let clip = transform * pole.push(1.);
+                if clip.w <= f32::EPSILON {
+                    return f32::NAN;
+                }
+                let screen_x = (clip.x / clip.w + 1.) / 2. * viewport.w as f32;
+                let screen_y = (1. - clip.y / clip.w) / 2. * viewport.h as f32;
+                ((screen_x - mouse.x as f32).powf(2.) + (screen_y - mouse.y as f32).powf(2.)).sqrt()
+            })
+            .enumerate()
+            .filter(|(_, dist)| dist.is_finite() && *dist < POLE_PICK_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    // Relinks the program when its shaders change
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<bool, gl_render::Error> {
+        let reloaded = self.program.reload_if_changed(res)?;
+        if reloaded {
+            blocks::bind(&self.program);
+        }
+        Ok(reloaded)
+    }
+
+    pub fn render(&self, gl: &gl::Gl) {
+        self.program.use_it();
+        self.vao.bind();
+
+        unsafe {
+            // Markers stay visible through the surface
+            gl.Disable(gl::DEPTH_TEST);
+            gl.DrawArrays(gl::LINES, 0, (self.count * 2) as i32);
+            gl.DrawArrays(gl::POINTS, (self.count * 2) as i32, self.count as i32);
+            gl.Enable(gl::DEPTH_TEST);
+        }
+        self.vao.unbind();
+    }
+}
@@ -0,0 +1,68 @@
+use std::str::FromStr;
+
+const WIREFRAME_SHADE: f32 = 0.3;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum RenderMode {
+    Filled,
+    Overlay,    // filled with wireframe on top
+    Wireframe,
+    Points,
+    Hidden,
+}
+
+impl RenderMode {
+    pub fn next(self) -> RenderMode {
+        match self {
+            RenderMode::Filled => RenderMode::Overlay,
+            RenderMode::Overlay => RenderMode::Wireframe,
+            RenderMode::Wireframe => RenderMode::Points,
+            RenderMode::Points => RenderMode::Hidden,
+            RenderMode::Hidden => RenderMode::Filled,
+        }
+    }
+
+    // Calls draw with primitive type once or twice (filled pass and darkened wireframe pass for overlay)
+    pub fn render<F: Fn(gl::types::GLenum)>(self, gl: &gl::Gl, draw: F) {
+        match self {
+            RenderMode::Filled => draw(gl::TRIANGLES),
+            RenderMode::Overlay => unsafe {
+                gl.Enable(gl::POLYGON_OFFSET_FILL);
+                gl.PolygonOffset(1., 1.);
+                draw(gl::TRIANGLES);
+                gl.Disable(gl::POLYGON_OFFSET_FILL);
+
+                gl.PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+                gl.BlendColor(WIREFRAME_SHADE, WIREFRAME_SHADE, WIREFRAME_SHADE, 1.);
+                gl.BlendFunc(gl::CONSTANT_COLOR, gl::ZERO);
+                draw(gl::TRIANGLES);
+                gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+                gl.PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            },
+            RenderMode::Wireframe => unsafe {
+                gl.PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+                draw(gl::TRIANGLES);
+                gl.PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            },
+            RenderMode::Points => draw(gl::POINTS),
+            RenderMode::Hidden => (),
+        }
+    }
+}
+
+impl FromStr for RenderMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "filled" => Ok(RenderMode::Filled),
+            "overlay" => Ok(RenderMode::Overlay),
+            "wireframe" => Ok(RenderMode::Wireframe),
+            "points" => Ok(RenderMode::Points),
+            "hidden" => Ok(RenderMode::Hidden),
+            _ => Err(format!("unknown render mode {}, expected filled, overlay, wireframe, points or hidden", s)),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::str::FromStr;
+use gl_render::{self, buffer, data, Viewport};
+use gl_render::text::Text;
+use gl_render::texture::{self, Texture2D, TextureFilter, TextureFormat};
+use resources::Resources;
+use crate::game_data::water::Water;
+
+const CELLS_UNIT: gl::types::GLuint = 0;
+const OVERLAY_HEIGHT: f32 = 0.35;   // part of the window height
+const MARGIN: i32 = 10;
+const LABEL_COLOR: (f32, f32, f32, f32) = (1., 1., 1., 1.);
+const LABEL_BACKGROUND: (f32, f32, f32, f32) = (0., 0., 0., 0.5);
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum SectionLayout {
+    Hidden,
+    Overlay,    // over the bottom of the scene
+    Split,      // scene in the top half of the window, slice in the bottom one
+}
+
+impl SectionLayout {
+    pub fn next(self) -> SectionLayout {
+        match self {
+            SectionLayout::Hidden => SectionLayout::Overlay,
+            SectionLayout::Overlay => SectionLayout::Split,
+            SectionLayout::Split => SectionLayout::Hidden,
+        }
+    }
+}
+
+impl FromStr for SectionLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hidden" => Ok(SectionLayout::Hidden),
+            "overlay" => Ok(SectionLayout::Overlay),
+            "split" => Ok(SectionLayout::Split),
+            _ => Err(format!("unknown section layout {}, expected hidden, overlay or split", s)),
+        }
+    }
+}
+
+// Axis running across the slice, the slice itself is at fixed position on the other one
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum SectionAxis {
+    X,
+    Z,
+}
+
+impl SectionAxis {
+    pub fn next(self) -> SectionAxis {
+        match self {
+            SectionAxis::X => SectionAxis::Z,
+            SectionAxis::Z => SectionAxis::X,
+        }
+    }
+}
+
+impl FromStr for SectionAxis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" => Ok(SectionAxis::X),
+            "z" => Ok(SectionAxis::Z),
+            _ => Err(format!("unknown section axis {}, expected x or z", s)),
+        }
+    }
+}
+
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = 0]
+    pos: data::f32_f32_f32_f32,     // (x, y) in NDC, (u, v) in the slice
+}
+
+impl From<(f32, f32, f32, f32)> for Vertex {
+    fn from(elem: (f32, f32, f32, f32)) -> Self {
+        Vertex { pos: elem.into() }
+    }
+}
+
+// 2d side view of one vertical slice of the water automaton: ground, water cells and empty space
+pub struct Section {
+    pub layout: SectionLayout,
+    pub axis: SectionAxis,
+    pub position: usize,
+    pub energy: bool,       // colour water by energy instead of plain blue
+    gl: gl::Gl,
+    program: gl_render::Program,
+    texture: Texture2D,
+    _vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+}
+
+impl Section {
+    pub fn new(res: &Resources, gl: &gl::Gl, layout: SectionLayout, axis: SectionAxis, position: usize,
+               energy: bool) -> Result<Section, failure::Error> {
+        let program = gl_render::Program::from_res(gl, res, "shaders/section")?;
+
+        let vertices: Vec<Vertex> = vec![
+            (-1., -1., 0., 0.).into(),
+            (1., -1., 1., 0.).into(),
+            (-1., 1., 0., 1.).into(),
+            (1., 1., 1., 1.).into(),
+        ];
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vertices);
+        vbo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        let texture = Texture2D::new(gl, TextureFormat::RG32F, TextureFilter::Nearest);
+        setup_program(&program);
+
+        Ok(Section { layout, axis, position, energy, gl: gl.clone(), program, texture, _vbo: vbo, vao })
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.layout != SectionLayout::Hidden
+    }
+
+    // Slice position stays within `size` cells
+    pub fn move_by(&mut self, cells: i32, size: usize) {
+        self.position = (self.position as i32 + cells).clamp(0, size as i32 - 1) as usize;
+    }
+
+    // Part of the window left to the 3d scene
+    pub fn scene_viewport(&self, window: &Viewport) -> Viewport {
+        let mut scene = Viewport::for_window(window.w, window.h);
+        if self.layout == SectionLayout::Split {
+            scene.h = window.h - window.h / 2;
+            scene.y = window.h / 2;
+        }
+        scene
+    }
+
+    // Where the slice is drawn, keeps square cells
+    fn rect(&self, window: &Viewport, columns: usize, rows: usize) -> Viewport {
+        let area_h = match self.layout {
+            SectionLayout::Split => window.h / 2,
+            _ => (window.h as f32 * OVERLAY_HEIGHT) as i32,
+        } - 2 * MARGIN;
+        let area_w = window.w - 2 * MARGIN;
+        let cell = (area_w as f32 / columns as f32).min(area_h as f32 / rows as f32);
+        let (w, h) = ((cell * columns as f32) as i32, (cell * rows as f32) as i32);
+        let mut rect = Viewport::for_window(w, h);
+        rect.x = (window.w - w) / 2;
+        rect.y = MARGIN;
+        rect
+    }
+
+    // Relinks the program when its shaders change
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<bool, gl_render::Error> {
+        let reloaded = self.program.reload_if_changed(res)?;
+        if reloaded {
+            setup_program(&self.program);
+        }
+        Ok(reloaded)
+    }
+
+    // Draws into the bottom of the window and leaves the whole window as viewport
+    pub fn render(&self, window: &Viewport, water: &Water) -> Result<(), texture::Error> {
+        if !self.is_visible() {
+            return Ok(());
+        }
+        let (columns, rows, cells) = water.section(self.axis, self.position);
+        self.texture.upload(columns as i32, rows as i32, &cells)?;
+
+        self.program.use_it();
+        self.program.set_uniform("energy_colors", &self.energy);
+        self.rect(window, columns, rows).use_it(&self.gl);
+        self.texture.bind(CELLS_UNIT);
+        unsafe {
+            self.gl.Disable(gl::DEPTH_TEST);
+            self.vao.bind();
+            self.gl.DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            self.vao.unbind();
+            self.gl.Enable(gl::DEPTH_TEST);
+        }
+        self.texture.unbind(CELLS_UNIT);
+        window.use_it(&self.gl);
+        Ok(())
+    }
+
+    // Which slice is shown, over its top left corner
+    pub fn render_label(&self, text: &Text, window: &Viewport, water: &Water) {
+        if !self.is_visible() {
+            return ;
+        }
+        let (columns, rows) = water.section_size();
+        let rect = self.rect(window, columns, rows);
+        let label = match self.axis {
+            SectionAxis::X => format!("Section along x at z = {}", self.position),
+            SectionAxis::Z => format!("Section along z at x = {}", self.position),
+        };
+        let label = match self.energy {
+            true => label + ", water by energy",
+            false => label,
+        };
+        let (x, y) = ((rect.x + 4) as f32, (window.h - rect.y - rect.h + 4) as f32);
+        text.render(window, &label, x, y, LABEL_COLOR, Some(LABEL_BACKGROUND));
+    }
+}
+
+// Uniforms which do not change, set again whenever the program is relinked
+fn setup_program(program: &gl_render::Program) {
+    program.set_uniform("cells", &(CELLS_UNIT as i32));
+}
@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+use resources::Resources;
+use crate::debug::failure_to_string;
+use crate::game_data::{GameData, source_resources};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Shader sources watched for changes
+pub struct ShaderWatch {
+    res: Resources,
+    last_poll: Instant,
+}
+
+impl ShaderWatch {
+    pub fn new(res: &Resources) -> ShaderWatch {
+        ShaderWatch { res: source_resources(res), last_poll: Instant::now() }
+    }
+
+    // Resources to reload from when it is time to look at the files again
+    fn poll(&mut self) -> Option<&Resources> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+        Some(&self.res)
+    }
+}
+
+impl GameData {
+    // Relinks programs whose shader files changed, a program failing to build keeps the old one
+    pub(super) fn reload_shaders(&mut self) {
+        let res = match self.shader_watch.as_mut().and_then(|watch| watch.poll()) {
+            Some(res) => res,
+            None => return,
+        };
+        let mut results = vec![
+            self.surface.reload_shaders(res),
+            self.poles.reload_shaders(res),
+            self.legend.reload_shaders(res),
+            self.section.reload_shaders(res),
+            self.map.reload_shaders(res),
+            self.hud.reload_shaders(res),
+        ];
+        results.extend(self.water.reload_shaders(res));
+        if let Some(comparison) = &mut self.comparison {
+            results.extend(comparison.reload_shaders(res));
+        }
+
+        // Compared terrain and water fail with the same errors as the main ones
+        let (mut reloaded, mut errors) = (false, Vec::new());
+        for result in results {
+            match result {
+                Ok(changed) => reloaded |= changed,
+                Err(e) => {
+                    let error = failure_to_string(e.into());
+                    if !errors.contains(&error) {
+                        println!("{}", error);
+                        errors.push(error);
+                    }
+                },
+            }
+        }
+        let failed = !errors.is_empty();
+        if reloaded {
+            println!("Shaders reloaded");
+        }
+        // Programs relinked before a failed one of the same object need their uniforms too
+        if reloaded || failed {
+            if let Err(e) = self.apply_color_map() {
+                println!("{}", failure_to_string(e));
+            }
+        }
+    }
+}
@@ -1,8 +1,6 @@
 use crate::gl_render::{self, buffer, data};
 use crate::resources::Resources;
-use std::ffi::{CString};
-use crate::camera::MVP;
-use failure::err_msg;
+use crate::game_data::blocks;
 use gl_render::uniform;
 
 
@@ -12,11 +10,13 @@ use gl_render::uniform;
 struct Vertex {
     #[location = 0]
     pos: data::f32_f32_f32,
+    #[location = 1]
+    normal: data::f32_f32_f32,
 }
 
-impl From<(f32, f32, f32)> for Vertex {
-    fn from(elem: (f32, f32, f32)) -> Self {
-        Vertex { pos: elem.into() }
+impl From<((f32, f32, f32), (f32, f32, f32))> for Vertex {
+    fn from(elem: ((f32, f32, f32), (f32, f32, f32))) -> Self {
+        Vertex { pos: elem.0.into(), normal: elem.1.into() }
     }
 }
 
@@ -30,6 +30,7 @@ pub struct Surface {
 impl Surface {
     pub fn new(res: &Resources, gl: &gl::Gl, grid: &[Vec<f32>]) -> Result<Surface, failure::Error> {
         let program = gl_render::Program::from_res(gl, res, "shaders/surface")?;
+        blocks::bind(&program);
 
         let vertices: Vec<Vertex> = generate_vertex_grid(grid)?;
         let indices: Vec<u32> = generate_indices(grid.len())?;
@@ -62,6 +63,15 @@ impl Surface {
         })
     }
 
+    // Contour interval is set again by GameData::apply_color_map
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<bool, gl_render::Error> {
+        let reloaded = self.program.reload_if_changed(res)?;
+        if reloaded {
+            blocks::bind(&self.program);
+        }
+        Ok(reloaded)
+    }
+
     pub fn render(&self, gl: &gl::Gl, mode: gl::types::GLenum) {
         self.program.use_it();
         self.vao.bind();
@@ -103,19 +113,30 @@ fn generate_vertex_grid(grid: &[Vec<f32>]) -> Result<Vec<Vertex>, failure::Error
     let mut coord: (f32, f32) = (-1. - step, -1. - step);   // (x, -z)
     let mut vertices: Vec<Vertex> = vec![];
 
-    for row in grid {
+    for (i, row) in grid.iter().enumerate() {
         assert_eq!(row.len(), grid.len());
         coord.1 += step;
-        for elem in row {
+        for (j, elem) in row.iter().enumerate() {
             coord.0 += step;
-            vertices.push((coord.0, *elem, coord.1).into());
+            let normal = grid_normal(grid, i, j, step);
+            vertices.push(((coord.0, *elem, coord.1), (normal.x, normal.y, normal.z)).into());
         }
         coord.0 = -1. - step;
     }
     Ok(vertices)
 }
 
-fn generate_indices(grid_size: usize) -> Result<Vec<u32>, failure::Error> {
+// Heightfield normal at grid point by central differences (one-sided on edges)
+pub fn grid_normal(grid: &[Vec<f32>], i: usize, j: usize, step: f32) -> na::Vector3<f32> {
+    let last = grid.len() - 1;
+    let (left, right) = (j.saturating_sub(1), std::cmp::min(j + 1, last));
+    let (top, bot) = (i.saturating_sub(1), std::cmp::min(i + 1, last));
+    let dh_dx = (grid[i][right] - grid[i][left]) / ((right - left) as f32 * step);
+    let dh_dz = (grid[bot][j] - grid[top][j]) / ((bot - top) as f32 * step);
+    na::Vector3::new(-dh_dx, 1., -dh_dz).normalize()
+}
+
+pub fn generate_indices(grid_size: usize) -> Result<Vec<u32>, failure::Error> {
     let mut indices: Vec<u32> = vec![];
     for i in 0..(grid_size - 1) {
         for j in 0..(grid_size - 1) {
@@ -130,15 +151,10 @@ fn generate_indices(grid_size: usize) -> Result<Vec<u32>, failure::Error> {
     Ok(indices)
 }
 
-impl uniform::HasUniform<MVP> for Surface {
-    fn apply_uniform(&self, gl: &gl::Gl, data: &MVP, name: &str) -> Result<(), failure::Error> {
-        self.program.use_it();
-        let name_cstr: CString = CString::new(name).map_err(err_msg)?;
-        let matrix: *const f32 = data.get_transform().as_slice().as_ptr();
-        unsafe {
-            let location = gl.GetUniformLocation(self.program.id(), name_cstr.as_ptr() as *const i8);
-            gl.UniformMatrix4fv(location, 1, gl::FALSE, matrix);
-        }
+
+impl uniform::HasUniform<f32> for Surface {
+    fn apply_uniform(&self, _gl: &gl::Gl, data: &f32, name: &str) -> Result<(), failure::Error> {
+        self.program.set_uniform(name, data);
         Ok(())
     }
 }
@@ -1,23 +1,35 @@
-extern crate chrono;
 extern crate rand;
 
 mod vertex;
-mod particle_shape;
+mod height_map;
+mod surface_mesh;
+mod particle_data;
 
-use vertex::Vertex;
-use gl_render::{buffer, uniform};
+use vertex::{Vertex, Instance};
+use gl_render::buffer;
 use resources::Resources;
-use crate::camera::MVP;
-use std::ffi::CString;
-use failure::err_msg;
 
-use chrono::prelude::*;
 use std::ops::{Index, IndexMut};
 use crate::game_data::GRID_WIDTH;
+use crate::game_data::brush::BrushArea;
+use crate::game_data::blocks;
+use crate::game_data::picking::clip_to_domain;
 use self::rand::Rng;
-use particle_shape::{ParticleShape, POINTS_PER_PARTICLE};
+use height_map::HeightMap;
+use surface_mesh::SurfaceMesh;
+use particle_data::{ParticleData, ParticleInfo, ParticleOrigin};
+pub use particle_data::WaterColor;
+use crate::game_data::section::SectionAxis;
 
 
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum WaterView {
+    Particles,
+    Surface,
+}
+
 #[derive(Debug)]
 #[derive(PartialEq)]
 enum Particle {
@@ -61,16 +73,50 @@ impl Direction {
     }
 }
 
+// Simulation parameters which can be changed while running
+#[derive(Copy, Clone, Debug)]
+pub struct WaterParams {
+    pub rain_rate: usize,       // particles tried per step
+    pub gravity: i32,           // energy gained by falling one cell
+    pub wave_height: f32,       // part of the grid height
+}
+
+impl WaterParams {
+    pub fn new() -> WaterParams {
+        WaterParams {
+            rain_rate: WATER_RAIN_ITERATIONS,
+            gravity: WATER_GRAVITY_FORCE,
+            wave_height: WATER_WAVE_HEIGHT,
+        }
+    }
+}
+
+// Set on every particles draw
+#[derive(Uniforms)]
+struct ParticleUniforms {
+    color_mode: i32,
+    step: f32,
+}
+
 pub struct Water {
+    pub params: WaterParams,
     water_level_max: usize,
     water_level: usize,
     grid: Vec<Vec<Vec<Particle>>>,
+    ground: Vec<Vec<usize>>,            // border cells at the bottom of every column
     locations: Vec<na::Vector3<usize>>,
-    ib_data: Vec<ParticleShape>,
+    infos: Vec<ParticleInfo>,
+    step: u32,
     program: gl_render::Program,
-    vbo: buffer::ArrayBuffer,
+    _vbo: buffer::ArrayBuffer,
     ebo: buffer::ElementArrayBuffer,
+    instances: buffer::SyncedBuffer<buffer::BufferTypeArray, Instance>,     // cells of `locations`
     vao: buffer::VertexArray,
+    height_map: HeightMap,
+    surface_mesh: SurfaceMesh,
+    particle_data: ParticleData,
+    pub view: WaterView,
+    color: WaterColor,
 }
 
 const WATER_GRID_WIDTH: usize = GRID_WIDTH;
@@ -78,6 +124,9 @@ const WATER_GIRD_HEIGHT: usize = GRID_WIDTH / 2;
 const WATER_RAIN_ITERATIONS: usize =
     ((WATER_GRID_WIDTH * WATER_GIRD_HEIGHT) as f32 * 0.0001) as usize + 1;
 const WATER_GRAVITY_FORCE: i32 = 10;
+const WATER_WAVE_HEIGHT: f32 = 2. / 3.;
+const GROUND_HEIGHTS_UNIT: gl::types::GLuint = 0;
+const PARTICLE_DATA_UNIT: gl::types::GLuint = 1;
 
 impl Water {
     pub fn new(res: &Resources, gl: &gl::Gl, grid_heights: &[Vec<f32>]) -> Result<Water, failure::Error> {
@@ -85,70 +134,210 @@ impl Water {
 
         let borders_h = WATER_GIRD_HEIGHT;
         let grid = generate_borders(grid_heights, borders_h);
+        let ground = ground_levels(&grid);
         let water_level_max = borders_h;
-        let vertices = generate_vertex_grid(grid_heights, borders_h);
 
-        let vbo = buffer::ArrayBuffer::new(&gl);
+        // Particle is a horizontal square at the bottom of its cell, every particle is an instance of it
+        let vertices: Vec<Vertex> = vec![(0., 0.).into(), (1., 0.).into(), (1., 1.).into(), (0., 1.).into()];
+        let indices: Vec<u32> = vec![0, 1, 2, 0, 3, 2];
+
+        let vbo = buffer::ArrayBuffer::new(gl);
         vbo.bind();
         vbo.static_draw_data(&vertices);
         vbo.unbind();
 
-        let ebo = buffer::ElementArrayBuffer::new(&gl);
+        let mut ebo = buffer::ElementArrayBuffer::new(gl);
+        ebo.bind();
+        ebo.static_draw_data(&indices);
+        ebo.set_elem_count(indices.len());
+        ebo.unbind();
+
+        let instances = buffer::SyncedBuffer::new(gl);
 
-        let vao = buffer::VertexArray::new(&gl);
+        let vao = buffer::VertexArray::new(gl);
         vao.bind();
         vbo.bind();
-        Vertex::vertex_attrib_pointers(&gl);
+        Vertex::vertex_attrib_pointers(gl);
+        instances.buffer().bind();
+        Instance::vertex_attrib_pointers(gl);
         ebo.bind();
-        vbo.unbind();
         vao.unbind();
+        instances.buffer().unbind();
         ebo.unbind();
 
+        let height_map = HeightMap::new(gl, grid_heights)?;
+        let surface_mesh = SurfaceMesh::new(res, gl)?;
+        setup_particles_program(&program);
+        setup_surface_program(&surface_mesh.program);
+        let particle_data = ParticleData::new(gl);
+        let view = WaterView::Particles;
+        let color = WaterColor::Depth;
+
         let water_level = 0;
         let locations = vec![];
-        let ib_data = vec![];
+        let infos = vec![];
+        let step = 0;
 
         Ok(Water {
+            params: WaterParams::new(),
             water_level_max, water_level,
-            grid, locations, ib_data,
-            program, vbo, ebo, vao,
+            grid, ground, locations, infos, step,
+            program, _vbo: vbo, ebo, instances, vao,
+            height_map, surface_mesh, particle_data, view, color,
         })
     }
 
+    // Particles and surface mesh programs, one result each, per draw uniforms are set in render
+    pub fn reload_shaders(&mut self, res: &Resources) -> Vec<Result<bool, gl_render::Error>> {
+        let particles = self.program.reload_if_changed(res);
+        if let Ok(true) = particles {
+            setup_particles_program(&self.program);
+        }
+        let surface = self.surface_mesh.program.reload_if_changed(res);
+        if let Ok(true) = surface {
+            setup_surface_program(&self.surface_mesh.program);
+        }
+        vec![particles, surface]
+    }
+
+    // Water is transparent: it is tested against depth of already drawn opaque objects but does not write it
     pub fn render(&self, gl: &gl::Gl, mode: gl::types::GLenum) {
-        self.program.use_it();
-        self.vao.bind();
+        self.height_map.bind(GROUND_HEIGHTS_UNIT);
+        unsafe {
+            gl.DepthMask(gl::FALSE);
+        }
+
+        match self.view {
+            WaterView::Particles => {
+                self.program.use_it();
+                self.apply_color_mode();
+                self.vao.bind();
+                unsafe {
+                    gl.DrawElementsInstanced(
+                        mode,
+                        self.ebo.get_elem_count() as i32,
+                        gl::UNSIGNED_INT,
+                        std::ptr::null(),
+                        self.instances.buffer().get_elem_count() as i32,
+                    );
+                }
+                self.vao.unbind();
+                self.particle_data.unbind(PARTICLE_DATA_UNIT);
+            },
+            WaterView::Surface => {
+                // Surface is extracted from the automaton right before drawing
+                self.surface_mesh.update(&self.grid);
+                self.surface_mesh.render(gl, mode);
+            },
+        }
 
         unsafe {
-            gl.DrawElements(
-                mode,
-                self.ebo.get_elem_count() as i32,
-                gl::UNSIGNED_INT,
-                0 as *const gl::types::GLvoid,
-            )
+            gl.DepthMask(gl::TRUE);
         }
-        self.vao.unbind();
+        self.height_map.unbind(GROUND_HEIGHTS_UNIT);
+    }
+
+    fn apply_color_mode(&self) {
+        self.particle_data.bind(PARTICLE_DATA_UNIT);
+
+        let uniforms = ParticleUniforms { color_mode: self.color as i32, step: self.step as f32 };
+        self.program.set_uniforms(&uniforms, "");
+    }
+
+    pub fn color(&self) -> WaterColor {
+        self.color
+    }
+
+    // Per particle data is not kept up to date while particles are coloured by depth
+    pub fn set_color(&mut self, color: WaterColor) {
+        let changed = self.color != color;
+        self.color = color;
+        if changed {
+            self.update_particle_data();
+        }
+    }
+
+    pub fn toggle_view(&mut self) {
+        self.view = match self.view {
+            WaterView::Particles => WaterView::Surface,
+            WaterView::Surface => WaterView::Particles,
+        };
     }
 
-    pub fn set_grid(&mut self, grid_heights: &[Vec<f32>]) {
+    pub fn set_grid(&mut self, grid_heights: &[Vec<f32>]) -> Result<(), failure::Error> {
         let borders_h = WATER_GIRD_HEIGHT;
         self.grid = generate_borders(grid_heights, borders_h);
+        self.ground = ground_levels(&self.grid);
         self.water_level_max = borders_h;
-        let vertices = generate_vertex_grid(grid_heights, borders_h);
-        self._update_vbo(&vertices);
+        self.height_map.set_grid(grid_heights)?;
 
-        self.update_ebo();
-        self.update_vao();
+        self.update_instances();
+        Ok(())
+    }
+
+    // Rebuilds borders of columns standing on grid points from area.
+    // Water pushed out by raised terrain moves up the column, the rest is kept as is
+    pub fn update_borders(&mut self, grid_heights: &[Vec<f32>], area: &BrushArea) -> Result<(), failure::Error> {
+        let step_h = 1. / (WATER_GIRD_HEIGHT - 1) as f32;
+        let size = self.grid.len();
+        self.height_map.set_grid(grid_heights)?;
+        let z_range = area.0.start.saturating_sub(1)..std::cmp::min(area.0.end, size);
+        let x_range = area.1.start.saturating_sub(1)..std::cmp::min(area.1.end, size);
+
+        for z in z_range {
+            for x in x_range.clone() {
+                let (top_left, top_right) = (grid_heights[z][x], grid_heights[z][x + 1]);
+                let (bot_left, bot_right) = (grid_heights[z + 1][x], grid_heights[z + 1][x + 1]);
+                let cur_height = ((top_left + top_right + bot_right + bot_left) / 4. / step_h).ceil() as usize;
+                let dir = get_direction(top_left, top_right, bot_left, bot_right);
+
+                for (y, particle) in self.grid[z][x].iter_mut().enumerate() {
+                    *particle = match particle {
+                        _ if y < cur_height => Particle::Border(dir),
+                        Particle::Border(_) => Particle::Empty,
+                        Particle::Water(any_dir, any_en) => Particle::Water(*any_dir, *any_en),
+                        Particle::Empty => Particle::Empty,
+                    }
+                }
+                self.ground[z][x] = ground_level(&self.grid[z][x]);
+            }
+        }
+
+        let gravity = self.params.gravity;
+        let mut keep: Vec<bool> = Vec::with_capacity(self.locations.len());
+        for loc in self.locations.iter_mut() {
+            let col = &mut self.grid[loc.z][loc.x];
+            if let Particle::Water(_, _) = col[loc.y] {
+                keep.push(true);
+                continue ;
+            }
+            match col.iter().position(|particle| *particle == Particle::Empty) {
+                Some(y) => {
+                    col[y] = Particle::Water(Direction::rand(), gravity);
+                    loc.y = y;
+                    keep.push(true);
+                }
+                None => keep.push(false),
+            }
+        }
+        let mut keep_iter = keep.iter();
+        self.locations.retain(|_| *keep_iter.next().unwrap());
+        let mut keep_iter = keep.iter();
+        self.infos.retain(|_| *keep_iter.next().unwrap());
+
+        self.update_instances();
+        Ok(())
     }
 
     pub fn modulate(&mut self) {
-        for (loc, square) in self.locations.iter_mut().zip(&mut self.ib_data) {
+        let gravity = self.params.gravity;
+        for loc in self.locations.iter_mut() {
             let x = loc.x;
             let y = loc.y;
             let z = loc.z;
             let (mut cur_dir, cur_energy) = match self.grid[z][x][y] {
                 Particle::Water(dir, energy) => (dir, energy),
-                _ => (Direction::East, WATER_GRAVITY_FORCE),
+                _ => (Direction::East, gravity),
             };
 
             let rnd_bool: bool = rand::random();
@@ -161,9 +350,8 @@ impl Water {
             match self.grid[z][x][y - 1] {
                 Particle::Empty => {
                     self.grid[z][x][y] = Particle::Empty;
-                    self.grid[z][x][y - 1] = Particle::Water(cur_dir, cur_energy + WATER_GRAVITY_FORCE);
+                    self.grid[z][x][y - 1] = Particle::Water(cur_dir, cur_energy + gravity);
                     loc.y = loc.y - 1;
-                    square.move_down();
                     continue ;
                 }
                 Particle::Border(dir) => {
@@ -183,20 +371,17 @@ impl Water {
                     self.grid[z][x][y] = Particle::Empty;
                     self.grid[z - 1][x][y] = Particle::Water(cur_dir, cur_energy - 1);
                     loc.z = loc.z - 1;
-                    square.move_north();
                 }
                 else if rnd_bool {
                     if (x > 0) && (self.grid[z][x - 1][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z][x - 1][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.x = loc.x - 1;
-                        square.move_west();
                     }
                     else if (x < WATER_GRID_WIDTH - 2) && (self.grid[z][x + 1][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z][x + 1][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.x = loc.x + 1;
-                        square.move_east();
                     }
                 }
                 else {
@@ -204,13 +389,11 @@ impl Water {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z][x + 1][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.x = loc.x + 1;
-                        square.move_east();
                     }
                     else if (x > 0) && (self.grid[z][x - 1][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z][x - 1][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.x = loc.x - 1;
-                        square.move_west();
                     }
                 }
             }
@@ -222,20 +405,17 @@ impl Water {
                     self.grid[z][x][y] = Particle::Empty;
                     self.grid[z + 1][x][y] = Particle::Water(cur_dir, cur_energy - 1);
                     loc.z = loc.z + 1;
-                    square.move_south();
                 }
                 else if rnd_bool {
                     if (x < WATER_GRID_WIDTH - 2) && (self.grid[z][x + 1][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z][x + 1][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.x = loc.x + 1;
-                        square.move_east();
                     }
                     else if (x > 0) && (self.grid[z][x - 1][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z][x - 1][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.x = loc.x - 1;
-                        square.move_west();
                     }
                 }
                 else {
@@ -243,13 +423,11 @@ impl Water {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z][x - 1][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.x = loc.x - 1;
-                        square.move_west();
                     }
                     else if (x < WATER_GRID_WIDTH - 2) && (self.grid[z][x + 1][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z][x + 1][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.x = loc.x + 1;
-                        square.move_east();
                     }
                 }
             }
@@ -261,20 +439,17 @@ impl Water {
                     self.grid[z][x][y] = Particle::Empty;
                     self.grid[z][x + 1][y] = Particle::Water(cur_dir, cur_energy - 1);
                     loc.x = loc.x + 1;
-                    square.move_east();
                 }
                 else if rnd_bool {
                     if (z < WATER_GRID_WIDTH - 2) && (self.grid[z + 1][x][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z + 1][x][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.z = loc.z + 1;
-                        square.move_south();
                     }
                     else if (z > 0) && (self.grid[z - 1][x][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z - 1][x][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.z = loc.z - 1;
-                        square.move_north();
                     }
                 }
                 else {
@@ -282,13 +457,11 @@ impl Water {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z - 1][x][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.z = loc.z - 1;
-                        square.move_north();
                     }
                     else if (z < WATER_GRID_WIDTH - 2) && (self.grid[z + 1][x][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z + 1][x][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.z = loc.z + 1;
-                        square.move_south();
                     }
                 }
             }
@@ -300,20 +473,17 @@ impl Water {
                     self.grid[z][x][y] = Particle::Empty;
                     self.grid[z][x - 1][y] = Particle::Water(cur_dir, cur_energy - 1);
                     loc.x = loc.x - 1;
-                    square.move_west();
                 }
                 else if rnd_bool {
                     if (z > 0) && (self.grid[z - 1][x][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z - 1][x][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.z = loc.z - 1;
-                        square.move_north();
                     }
                     else if (z < WATER_GRID_WIDTH - 2) && (self.grid[z + 1][x][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z + 1][x][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.z = loc.z + 1;
-                        square.move_south();
                     }
                 }
                 else {
@@ -321,27 +491,25 @@ impl Water {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z + 1][x][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.z = loc.z + 1;
-                        square.move_south();
                     }
                     else if (z > 0) && (self.grid[z - 1][x][y] == Particle::Empty) {
                         self.grid[z][x][y] = Particle::Empty;
                         self.grid[z - 1][x][y] = Particle::Water(cur_dir, cur_energy - 3);
                         loc.z = loc.z - 1;
-                        square.move_north();
                     }
                 }
             }
         }
 
+        self.step += 1;
         self.update_water_level();
-        self.update_ebo();
-        self.update_vao();
+        self.update_instances();
     }
 
     pub fn flush(&mut self) {
         self.water_level = 0;
-        self.ib_data.clear();
         self.locations.clear();
+        self.infos.clear();
         for side in &mut self.grid {
             for col in side {
                 for particle in col {
@@ -355,8 +523,7 @@ impl Water {
         }
         self.water_level = 0;
 
-        self.update_ebo();
-        self.update_vao();
+        self.update_instances();
     }
 
     pub fn _loop_add_water(&mut self) {
@@ -373,19 +540,17 @@ impl Water {
     }
 
     fn fill_water_level(&mut self, level: usize) {
-        let xz_size = WATER_GRID_WIDTH as u32;
-        let y_size = WATER_GIRD_HEIGHT as u32;
         let mut cur_water_idx_x;
         let mut cur_water_idx_z = 0;
+        let info = ParticleInfo { origin: ParticleOrigin::Level, born: self.step };
 
         for side in &mut self.grid {
             cur_water_idx_x = 0;
             for col in side {
                 *col.index_mut(level) = match col.index(level) {
                     Particle::Empty => {
-                        add_particle(&mut self.locations, &mut self.ib_data,
-                                     cur_water_idx_x, level, cur_water_idx_z,
-                                     xz_size, y_size);
+                        self.locations.push(na::Vector3::new(cur_water_idx_x, level, cur_water_idx_z));
+                        self.infos.push(info);
                         Particle::Water(Direction::East, 0)
                     },
                     Particle::Water(any_dir, any_en) => Particle::Water(*any_dir, *any_en),
@@ -395,8 +560,7 @@ impl Water {
             }
             cur_water_idx_z += 1;
         }
-        self.update_ebo();
-        self.update_vao();
+        self.update_instances();
     }
 
     fn update_water_level(&mut self) {
@@ -415,25 +579,25 @@ impl Water {
         if need_up {
             self.water_level = std::cmp::min(cur_water_level + 1, self.water_level_max);
             if self.water_level > 3 {
-                let v = self.locations.iter().zip(&self.ib_data)
-                    .fold((vec![], vec![]), |mut acc, (location, index)| {
+                let v = self.locations.iter().zip(&self.infos)
+                    .fold((vec![], vec![]), |mut acc, (location, info)| {
                         if !((location.z > 0 && location.z < GRID_WIDTH - 2)
                             && (location.x > 0 && location.x < GRID_WIDTH - 2)
                             && (location.y < self.water_level - 1))
                         {
                             acc.0.push(*location);
-                            acc.1.push(*index);
+                            acc.1.push(*info);
                         }
                         acc
                     });
                 self.locations = v.0;
-                self.ib_data = v.1;
+                self.infos = v.1;
             }
         }
     }
 
     pub fn add_rain_particles(&mut self) {
-        for _i in 0..WATER_RAIN_ITERATIONS {
+        for _i in 0..self.params.rain_rate {
             let x = rand::thread_rng().gen_range(0..WATER_GRID_WIDTH - 2);
             let z = rand::thread_rng().gen_range(0..WATER_GRID_WIDTH - 2);
             let y   = WATER_GIRD_HEIGHT - 2;
@@ -445,16 +609,15 @@ impl Water {
             };
 
             if self.grid[z][x][y] == Particle::Empty {
-                self.grid[z][x][y] = Particle::Water(dir, WATER_GRAVITY_FORCE);
-                self.add_particle(x, y, z);
+                self.grid[z][x][y] = Particle::Water(dir, self.params.gravity);
+                self.add_particle(x, y, z, ParticleOrigin::Rain);
             }
         }
-        self.update_ebo();
-        self.update_vao();
+        self.update_instances();
     }
 
     pub fn add_wave_particles(&mut self, dir: Direction) {
-        let y_range = 0..(WATER_GIRD_HEIGHT / 3 * 2) as usize;
+        let y_range = 0..(WATER_GIRD_HEIGHT as f32 * self.params.wave_height) as usize;
 
         let (z_range, x_range) = match dir {
             Direction::South => ((WATER_GRID_WIDTH - 2 .. WATER_GRID_WIDTH - 1), (0..WATER_GRID_WIDTH - 1)),
@@ -468,56 +631,152 @@ impl Water {
                 for y in y_range.clone() {
                     if self.grid[z][x][y] == Particle::Empty {
                         self.grid[z][x][y] = Particle::Water(!dir, (GRID_WIDTH * GRID_WIDTH) as i32);
-                        self.add_particle(x, y, z);
+                        self.add_particle(x, y, z, ParticleOrigin::Wave);
                     }
                 }
             }
         }
 
-        self.update_ebo();
-        self.update_vao();
+        self.update_instances();
+    }
+
+    // Puts water on top of every column within radius around (x, z)
+    pub fn add_water_at(&mut self, x: usize, z: usize, radius: usize) {
+        let size = self.grid.len();
+        for cur_z in z.saturating_sub(radius)..std::cmp::min(z + radius + 1, size) {
+            for cur_x in x.saturating_sub(radius)..std::cmp::min(x + radius + 1, size) {
+                let col = &mut self.grid[cur_z][cur_x];
+                if let Some(y) = col.iter().position(|particle| *particle == Particle::Empty) {
+                    col[y] = Particle::Water(Direction::rand(), self.params.gravity);
+                    self.add_particle(cur_x, y, cur_z, ParticleOrigin::Placed);
+                }
+            }
+        }
+        self.update_instances();
+    }
+
+    // Finds first non empty cell (x, y, z) hit by ray origin + t * dir, t in [0;1]
+    pub fn ray_cast(&self, origin: &na::Point3<f32>, dir: &na::Vector3<f32>) -> Option<na::Vector3<usize>> {
+        let xz_step = 2. / (WATER_GRID_WIDTH - 1) as f32;
+        let y_step = 1. / (WATER_GIRD_HEIGHT - 1) as f32;
+        let (t_min, t_max) = clip_to_domain(origin, dir)?;
+        let t_step = xz_step.min(y_step) / 2. / dir.norm();
+        let size = self.grid.len();
+
+        let mut t = t_min;
+        while t <= t_max {
+            let point = origin + dir * t;
+            let x = ((point.x + 1.) / xz_step).floor() as usize;
+            let z = ((point.z + 1.) / xz_step).floor() as usize;
+            let y = (point.y / y_step).round() as usize;
+            if x < size && z < size && y < WATER_GIRD_HEIGHT && self.grid[z][x][y] != Particle::Empty {
+                return Some(na::Vector3::new(x, y, z));
+            }
+            t += t_step;
+        }
+        None
+    }
+
+    pub fn cell_center(&self, cell: &na::Vector3<usize>) -> na::Point3<f32> {
+        let xz_step = 2. / (WATER_GRID_WIDTH - 1) as f32;
+        let y_step = 1. / (WATER_GIRD_HEIGHT - 1) as f32;
+        na::Point3::new(
+            -1. + (cell.x as f32 + 0.5) * xz_step,
+            cell.y as f32 * y_step,
+            -1. + (cell.z as f32 + 0.5) * xz_step)
+    }
+
+    pub fn get_water_level(&self) -> (usize, usize) {
+        (self.water_level, self.water_level_max)
     }
 
-    fn add_particle(&mut self, x: usize, y: usize, z: usize) {
-        add_particle(&mut self.locations, &mut self.ib_data,
-                     x, y, z,
-                     WATER_GRID_WIDTH as u32, WATER_GIRD_HEIGHT as u32);
+    // Particles still moving, the ones sunk below the water level are not tracked
+    pub fn get_particle_count(&self) -> usize {
+        self.locations.len()
     }
 
-    fn _update_vbo(&self, vertices: &[Vertex]) {
-        self.vbo.bind();
-        self.vbo.static_draw_data(vertices);
-        self.vbo.unbind();
+    pub fn column_depth(&self, x: usize, z: usize) -> usize {
+        column_depth(&self.grid[z][x])
     }
 
-    fn update_ebo(&mut self) {
-        self.ebo.bind();
-        self.ebo.dynamic_draw_data(&self.ib_data);
-        self.ebo.set_elem_count(self.ib_data.len() * POINTS_PER_PARTICLE);
-        self.ebo.unbind();
+    // (ground height, water depth) of a column in parts of the grid height
+    pub fn column(&self, x: usize, z: usize) -> (f32, f32) {
+        let step_h = 1. / (WATER_GIRD_HEIGHT - 1) as f32;
+        (self.ground[z][x] as f32 * step_h, self.column_depth(x, z) as f32 * step_h)
     }
 
-    fn update_vao(&self) {
-        self.vao.bind();
-        self.vbo.bind();
-        self.ebo.bind();
-        self.vbo.unbind();
-        self.vao.unbind();
-        self.ebo.unbind();
+    // Columns along a side and (ground height, water depth) of every column, rows go along z
+    pub fn map(&self) -> (usize, Vec<[f32; 2]>) {
+        (self.grid.len(), depth_map(&self.grid, &self.ground))
     }
+
+    // (columns, rows) of a vertical slice
+    pub fn section_size(&self) -> (usize, usize) {
+        (self.grid.len(), self.grid[0][0].len())
+    }
+
+    // Vertical slice at `position` with `axis` running across it as (columns, rows, cells),
+    // rows go from the bottom, a cell is (kind, energy) with kind 0 for empty, 1 for ground and 2 for water
+    pub fn section(&self, axis: SectionAxis, position: usize) -> (usize, usize, Vec<[f32; 2]>) {
+        let (columns, rows) = self.section_size();
+        let mut cells: Vec<[f32; 2]> = Vec::with_capacity(columns * rows);
+        for y in 0..rows {
+            for column in 0..columns {
+                let particle = match axis {
+                    SectionAxis::X => &self.grid[position][column][y],
+                    SectionAxis::Z => &self.grid[column][position][y],
+                };
+                cells.push(match particle {
+                    Particle::Empty => [0., 0.],
+                    Particle::Border(_) => [1., 0.],
+                    Particle::Water(_, energy) => [2., (*energy).max(0) as f32],
+                });
+            }
+        }
+        (columns, rows, cells)
+    }
+
+    fn add_particle(&mut self, x: usize, y: usize, z: usize, origin: ParticleOrigin) {
+        self.locations.push(na::Vector3::new(x, y, z));
+        self.infos.push(ParticleInfo { origin, born: self.step });
+    }
+
+    // Particles are drawn from their cells, so the upload grows with particle count only
+    // Only particles that moved, appeared or disappeared since the last call are uploaded
+    fn update_instances(&mut self) {
+        self.instances.assign(self.locations.iter().map(Instance::from));
+        self.instances.upload();
+        self.update_particle_data();
+    }
+
+    // Data the particles are coloured by when it is not depth
+    fn update_particle_data(&mut self) {
+        if self.color == WaterColor::Depth {
+            return;
+        }
+        let grid = &self.grid;
+        self.particle_data.update(self.locations.iter().zip(&self.infos)
+            .map(|(loc, info)| {
+                let (dir, energy) = match grid[loc.z][loc.x][loc.y] {
+                    Particle::Water(dir, energy) => (dir, energy),
+                    _ => (Direction::East, 0),
+                };
+                [energy.max(0) as f32, dir as i32 as f32, info.origin as i32 as f32, info.born as f32]
+            }));
+    }
+}
+
+// Block bindings and texture units, set again whenever a program is relinked
+fn setup_particles_program(program: &gl_render::Program) {
+    blocks::bind(program);
+    program.set_uniform("ground_heights", &(GROUND_HEIGHTS_UNIT as i32));
+    program.set_uniform("particle_data", &(PARTICLE_DATA_UNIT as i32));
+    program.set_uniform("cell_size", &(2. / (WATER_GRID_WIDTH - 1) as f32, 1. / (WATER_GIRD_HEIGHT - 1) as f32));
 }
 
-fn add_particle(locations: &mut Vec<na::Vector3<usize>>, ib_data: &mut Vec<ParticleShape>,
-                x: usize, y: usize, z: usize,
-                xz_size: u32, y_size: u32) {
-    locations.push(na::Vector3::new(x, y, z));
-    ib_data.push(ParticleShape::new(
-        x as u32,
-        y as u32,
-        z as u32,
-        xz_size,
-        y_size)
-    );
+fn setup_surface_program(program: &gl_render::Program) {
+    blocks::bind(program);
+    program.set_uniform("ground_heights", &(GROUND_HEIGHTS_UNIT as i32));
 }
 
 fn generate_borders(grid_heights: &[Vec<f32>], borders_h: usize) -> Vec<Vec<Vec<Particle>>> {
@@ -552,6 +811,30 @@ fn generate_borders(grid_heights: &[Vec<f32>], borders_h: usize) -> Vec<Vec<Vec<
     borders
 }
 
+fn ground_levels(grid: &[Vec<Vec<Particle>>]) -> Vec<Vec<usize>> {
+    grid.iter()
+        .map(|side| side.iter().map(|column| ground_level(column)).collect())
+        .collect()
+}
+
+// Borders are always at the bottom of a column
+fn ground_level(column: &[Particle]) -> usize {
+    column.iter().take_while(|particle| matches!(particle, Particle::Border(_))).count()
+}
+
+// Counted over cells, particles sunk below the water level are no longer in `locations`
+fn column_depth(column: &[Particle]) -> usize {
+    column.iter().filter(|particle| matches!(particle, Particle::Water(_, _))).count()
+}
+
+// (ground height, water depth) of every column in parts of the grid height, ground comes from `ground_levels`
+fn depth_map(grid: &[Vec<Vec<Particle>>], ground: &[Vec<usize>]) -> Vec<[f32; 2]> {
+    let step_h = 1. / (WATER_GIRD_HEIGHT - 1) as f32;
+    grid.iter().flatten().zip(ground.iter().flatten())
+        .map(|(column, ground)| [*ground as f32 * step_h, column_depth(column) as f32 * step_h])
+        .collect()
+}
+
 fn get_direction(top_left: f32, top_right: f32, bot_left: f32, bot_right: f32) -> Direction {
     let top = top_left + top_right;
     let bot = bot_left + bot_right;
@@ -572,41 +855,23 @@ fn get_direction(top_left: f32, top_right: f32, bot_left: f32, bot_right: f32) -
     }
 }
 
-fn generate_vertex_grid(grid_heights: &[Vec<f32>], borders_h: usize) -> Vec<Vertex> {
-    let start = Utc::now();
-
-    let mut vertices: Vec<Vertex> = Vec::with_capacity(WATER_GRID_WIDTH * WATER_GRID_WIDTH * WATER_GIRD_HEIGHT);
-    let mut cur_coord = na::Vector3::new(-1., 0., -1.);
-    let xz_step = 2. / (WATER_GRID_WIDTH - 1) as f32;
-    let y_step = 1. / (WATER_GIRD_HEIGHT - 1) as f32;
-
-    for row in grid_heights {
-        cur_coord.x = -1.;
-        for _elem in row {
-            cur_coord.y = 0.;
-            for _i in 0..borders_h {
-                vertices.push(cur_coord.into());
-                cur_coord.y += y_step;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_counts_water_below_level() {
+        let level = 6;
+        let mut grid = generate_borders(&vec![vec![0.; 4]; 4], WATER_GIRD_HEIGHT);
+        for column in grid.iter_mut().flatten() {
+            for particle in column.iter_mut().take(level) {
+                *particle = Particle::Water(Direction::North, 0);
             }
-            cur_coord.x += xz_step;
         }
-        cur_coord.z += xz_step;
-    }
-
-    let end = Utc::now();
-    println!("Gen Water Vertex Grid taken: {} ms", (end - start).num_milliseconds());
-    vertices
-}
-
-impl uniform::HasUniform<MVP> for Water {
-    fn apply_uniform(&self, gl: &gl::Gl, data: &MVP, name: &str) -> Result<(), failure::Error> {
-        self.program.use_it();
-        let name_cstr: CString = CString::new(name).map_err(err_msg)?;
-        let matrix: *const f32 = data.get_transform().as_slice().as_ptr();
-        unsafe {
-            let location = gl.GetUniformLocation(self.program.id(), name_cstr.as_ptr() as *const i8);
-            gl.UniformMatrix4fv(location, 1, gl::FALSE, matrix);
+        let ground = ground_levels(&grid);
+        let step_h = 1. / (WATER_GIRD_HEIGHT - 1) as f32;
+        for cell in depth_map(&grid, &ground) {
+            assert_eq!(cell, [0., level as f32 * step_h]);
         }
-        Ok(())
     }
 }
@@ -0,0 +1,29 @@
+use gl_render::texture::{self, Texture2D, TextureFilter, TextureFormat};
+
+// Surface heights as a single channel float texture, lets water shader know the depth under each particle
+pub struct HeightMap {
+    texture: Texture2D,
+}
+
+impl HeightMap {
+    pub fn new(gl: &gl::Gl, grid_heights: &[Vec<f32>]) -> Result<HeightMap, texture::Error> {
+        let height_map = HeightMap { texture: Texture2D::new(gl, TextureFormat::R32F, TextureFilter::Linear) };
+        height_map.set_grid(grid_heights)?;
+        Ok(height_map)
+    }
+
+    // Row i of the grid becomes texture row i (z axis), column j - texel j (x axis)
+    pub fn set_grid(&self, grid_heights: &[Vec<f32>]) -> Result<(), texture::Error> {
+        let size = grid_heights.len() as i32;
+        let data: Vec<f32> = grid_heights.iter().flatten().copied().collect();
+        self.texture.upload(size, size, &data)
+    }
+
+    pub fn bind(&self, unit: gl::types::GLuint) {
+        self.texture.bind(unit);
+    }
+
+    pub fn unbind(&self, unit: gl::types::GLuint) {
+        self.texture.unbind(unit);
+    }
+}
@@ -0,0 +1,109 @@
+use std::str::FromStr;
+use gl_render::buffer::{BufferTypeTexture, SyncedBuffer};
+
+// What water particles are coloured by
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum WaterColor {
+    Depth,
+    Energy,
+    Direction,
+    Origin,
+    Age,
+}
+
+impl WaterColor {
+    pub fn next(self) -> WaterColor {
+        match self {
+            WaterColor::Depth => WaterColor::Energy,
+            WaterColor::Energy => WaterColor::Direction,
+            WaterColor::Direction => WaterColor::Origin,
+            WaterColor::Origin => WaterColor::Age,
+            WaterColor::Age => WaterColor::Depth,
+        }
+    }
+}
+
+impl FromStr for WaterColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "depth" => Ok(WaterColor::Depth),
+            "energy" => Ok(WaterColor::Energy),
+            "direction" => Ok(WaterColor::Direction),
+            "origin" => Ok(WaterColor::Origin),
+            "age" => Ok(WaterColor::Age),
+            _ => Err(format!("unknown water colour {}, expected depth, energy, direction, origin or age", s)),
+        }
+    }
+}
+
+// How the particle got into the grid
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum ParticleOrigin {
+    Level,
+    Rain,
+    Wave,
+    Placed,
+}
+
+// Per particle state which is not a part of the automaton grid
+#[derive(Copy, Clone)]
+pub struct ParticleInfo {
+    pub origin: ParticleOrigin,
+    pub born: u32,      // simulation step the particle appeared on
+}
+
+// Per particle (energy, direction, origin, born) floats in a texture buffer,
+// element i belongs to particle instance i
+pub struct ParticleData {
+    gl: gl::Gl,
+    data: SyncedBuffer<BufferTypeTexture, [f32; 4]>,
+    texture: gl::types::GLuint,
+}
+
+impl ParticleData {
+    pub fn new(gl: &gl::Gl) -> ParticleData {
+        let data = SyncedBuffer::new(gl);
+        let mut texture: gl::types::GLuint = 0;
+        unsafe {
+            gl.GenTextures(1, &mut texture);
+            gl.BindTexture(gl::TEXTURE_BUFFER, texture);
+            gl.TexBuffer(gl::TEXTURE_BUFFER, gl::RGBA32F, data.buffer().id());
+            gl.BindTexture(gl::TEXTURE_BUFFER, 0);
+        }
+        ParticleData { gl: gl.clone(), data, texture }
+    }
+
+    // Only particles whose data changed since the last update are uploaded
+    pub fn update<I: IntoIterator<Item = [f32; 4]>>(&mut self, data: I) {
+        self.data.assign(data);
+        self.data.upload();
+    }
+
+    pub fn bind(&self, unit: gl::types::GLuint) {
+        unsafe {
+            self.gl.ActiveTexture(gl::TEXTURE0 + unit);
+            self.gl.BindTexture(gl::TEXTURE_BUFFER, self.texture);
+        }
+    }
+
+    pub fn unbind(&self, unit: gl::types::GLuint) {
+        unsafe {
+            self.gl.ActiveTexture(gl::TEXTURE0 + unit);
+            self.gl.BindTexture(gl::TEXTURE_BUFFER, 0);
+        }
+    }
+}
+
+impl Drop for ParticleData {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.DeleteTextures(1, &self.texture);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::gl_render::{self, buffer, data};
+use crate::resources::Resources;
+use crate::game_data::water::{Particle, WATER_GIRD_HEIGHT, WATER_GRID_WIDTH};
+use crate::game_data::surface::{generate_indices, grid_normal};
+
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = 0]
+    pos: data::f32_f32_f32,
+    #[location = 1]
+    normal_wet: data::f32_f32_f32_f32,  // normal and 1. if vertex touches water, 0. otherwise
+}
+
+// Continuous water surface: one vertex per water grid corner at the mean water height of adjacent columns
+pub struct SurfaceMesh {
+    pub program: gl_render::Program,
+    vbo: buffer::ArrayBuffer,
+    ebo: buffer::ElementArrayBuffer,
+    vao: buffer::VertexArray,
+}
+
+impl SurfaceMesh {
+    pub fn new(res: &Resources, gl: &gl::Gl) -> Result<SurfaceMesh, failure::Error> {
+        let program = gl_render::Program::from_res(gl, res, "shaders/water_surface")?;
+        let indices: Vec<u32> = generate_indices(WATER_GRID_WIDTH)?;
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+
+        let mut ebo = buffer::ElementArrayBuffer::new(gl);
+        ebo.bind();
+        ebo.static_draw_data(&indices);
+        ebo.set_elem_count(indices.len());
+        ebo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        ebo.bind();
+        vbo.unbind();
+        vao.unbind();
+        ebo.unbind();
+
+        Ok(SurfaceMesh { program, vbo, ebo, vao })
+    }
+
+    // Mesh is rebuilt for every frame it is drawn in
+    pub fn update(&self, grid: &[Vec<Vec<Particle>>]) {
+        let vertices = generate_vertices(grid);
+        self.vbo.bind();
+        self.vbo.stream_draw_data(&vertices);
+        self.vbo.unbind();
+    }
+
+    pub fn render(&self, gl: &gl::Gl, mode: gl::types::GLenum) {
+        self.program.use_it();
+        self.vao.bind();
+
+        unsafe {
+            gl.DrawElements(
+                mode,
+                self.ebo.get_elem_count() as i32,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            )
+        }
+        self.vao.unbind();
+    }
+}
+
+// Height of the water standing on the ground in column (in cells) and whether there is any
+fn column_level(col: &[Particle]) -> (usize, bool) {
+    let ground = col.iter().position(|particle| !matches!(particle, Particle::Border(_))).unwrap_or(col.len());
+    let water = col.iter().skip(ground).take_while(|particle| matches!(particle, Particle::Water(_, _))).count();
+    match water {
+        0 => (ground, false),
+        _ => (ground + water - 1, true),
+    }
+}
+
+fn generate_vertices(grid: &[Vec<Vec<Particle>>]) -> Vec<Vertex> {
+    let columns = grid.len();
+    let size = columns + 1;
+    let levels: Vec<Vec<(usize, bool)>> = grid.iter()
+        .map(|side| side.iter().map(|col| column_level(col)).collect())
+        .collect();
+
+    let y_step = 1. / (WATER_GIRD_HEIGHT - 1) as f32;
+    let xz_step = 2. / (WATER_GRID_WIDTH - 1) as f32;
+    let mut heights: Vec<Vec<f32>> = vec![vec![0.; size]; size];
+    let mut wet: Vec<Vec<bool>> = vec![vec![false; size]; size];
+
+    for i in 0..size {
+        for j in 0..size {
+            let neighbours: Vec<(usize, bool)> = [(i.wrapping_sub(1), j.wrapping_sub(1)), (i.wrapping_sub(1), j),
+                                                  (i, j.wrapping_sub(1)), (i, j)].iter()
+                .filter(|(z, x)| *z < columns && *x < columns)
+                .map(|(z, x)| levels[*z][*x])
+                .collect();
+            let wet_levels: Vec<usize> = neighbours.iter().filter(|(_, is_wet)| *is_wet).map(|(level, _)| *level).collect();
+            let (sum, count) = match wet_levels.len() {
+                0 => (neighbours.iter().map(|(level, _)| *level).sum::<usize>(), neighbours.len()),
+                n => (wet_levels.iter().sum::<usize>(), n),
+            };
+            heights[i][j] = sum as f32 / count as f32 * y_step;
+            wet[i][j] = !wet_levels.is_empty();
+        }
+    }
+
+    let mut vertices: Vec<Vertex> = Vec::with_capacity(size * size);
+    for i in 0..size {
+        for j in 0..size {
+            let normal = grid_normal(&heights, i, j, xz_step);
+            let is_wet = match wet[i][j] { true => 1., false => 0. };
+            vertices.push(Vertex {
+                pos: (-1. + j as f32 * xz_step, heights[i][j], -1. + i as f32 * xz_step).into(),
+                normal_wet: (normal.x, normal.y, normal.z, is_wet).into(),
+            });
+        }
+    }
+    vertices
+}
@@ -1,21 +1,32 @@
 use gl_render::{data};
 
+// Corner of the particle quad in cells from the particle corner, (x, z)
 #[derive(VertexAttribPointers)]
 #[derive(Copy, Clone, Debug)]
 #[repr(C, packed)]
 pub struct Vertex {
     #[location = 0]
-    pos: data::f32_f32_f32,
+    corner: data::f32_f32,
 }
 
-impl From<(f32, f32, f32)> for Vertex {
-    fn from(elem: (f32, f32, f32)) -> Self {
-        Vertex { pos: elem.into() }
+impl From<(f32, f32)> for Vertex {
+    fn from(elem: (f32, f32)) -> Self {
+        Vertex { corner: elem.into() }
     }
 }
 
-impl From<na::Vector3<f32>> for Vertex {
-    fn from(v: na::Vector3<f32>) -> Self {
-        Vertex { pos: (v.x, v.y, v.z).into() }
+// Particle drawn as one instance of the quad
+#[derive(VertexAttribPointers)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(C, packed)]
+pub struct Instance {
+    #[location = 1]
+    #[divisor = 1]
+    cell: data::u32_u32_u32,    // (x, y, z) in the water grid
+}
+
+impl From<&na::Vector3<usize>> for Instance {
+    fn from(cell: &na::Vector3<usize>) -> Self {
+        Instance { cell: (cell.x as u32, cell.y as u32, cell.z as u32).into() }
     }
-}
\ No newline at end of file
+}
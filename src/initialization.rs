@@ -8,11 +8,12 @@ pub fn set_gl_attr(video: &VideoSubsystem) {
     gl_attr.set_context_version(4, 1);
 }
 
-pub fn create_window(video: &VideoSubsystem) -> Result<Window, failure::Error> {
-    let window = video
-        .window("Water cellular automaton", 900, 700)   // TODO: add to config
-        .opengl()
-        .resizable()
-        .build().map_err(err_msg)?;
+pub fn create_window(video: &VideoSubsystem, hidden: bool) -> Result<Window, failure::Error> {
+    let mut builder = video.window("Water cellular automaton", 900, 700);   // TODO: add to config
+    builder.opengl().resizable();
+    if hidden {
+        builder.hidden();
+    }
+    let window = builder.build().map_err(err_msg)?;
     Ok(window)
 }
\ No newline at end of file
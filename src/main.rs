@@ -16,26 +16,43 @@ use std::env;
 mod debug;
 mod initialization;
 mod camera;
+mod camera_path;
+mod config;
+mod capture;
 mod game_data;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let grid_path = match args.len() {
-        1 => "grids/grid.mod1".to_owned(),
-        2 => "grids/".to_owned() + &args[1],
-        _ => { println!("Too much arguments"); return; }
-    };
+    let mut grid_path: Option<String> = None;
+    let mut headless_frames: Option<usize> = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" => match args.next().and_then(|frames| frames.parse::<usize>().ok()) {
+                Some(frames) => headless_frames = Some(frames),
+                None => { println!("--headless expects number of frames to record"); return; }
+            },
+            _ if grid_path.is_some() => { println!("Too much arguments"); return; }
+            name => grid_path = Some("grids/".to_owned() + name),
+        }
+    }
+    let grid_path = grid_path.unwrap_or_else(|| "grids/grid.mod1".to_owned());
 
-    if let Err(e) = run(&grid_path) {
+    if let Err(e) = run(&grid_path, headless_frames) {
         println!("{}", debug::failure_to_string(e));
     }
 }
 
-fn run(grid_path: &str) -> Result<(), failure::Error> {
+fn run(grid_path: &str, headless_frames: Option<usize>) -> Result<(), failure::Error> {
+    if headless_frames.is_some() {
+        // The window is hidden and frames are drawn into an offscreen framebuffer, but SDL still needs a video
+        // driver working without a display. The offscreen one (EGL) is asked for here, the hint is read only by
+        // SDL 2.0.22 and newer and SDL_VIDEODRIVER from the environment takes precedence over it
+        sdl2::hint::set("SDL_VIDEODRIVER", "offscreen");
+    }
     let sdl = sdl2::init().map_err(err_msg)?;
     let video_subsystem = sdl.video().map_err(err_msg)?;
     set_gl_attr(&video_subsystem);
-    let window = create_window(&video_subsystem).map_err(err_msg)?;
+    let window = create_window(&video_subsystem, headless_frames.is_some()).map_err(err_msg)?;
     let _gl_context = window.gl_create_context().map_err(err_msg)?;
     let gl = gl::Gl::load_with(|s| video_subsystem.gl_get_proc_address(s) as *const std::os::raw::c_void);
     let mut event_pump = sdl.event_pump().map_err(err_msg)?;
@@ -44,6 +61,9 @@ fn run(grid_path: &str) -> Result<(), failure::Error> {
 
     let mut gd = GameData::new(&gl, &res, grid_path).map_err(err_msg)?;
     gd.init();
+    if let Some(frames) = headless_frames {
+        gd.start_headless(frames)?;
+    }
 
     'main: loop {
         gd.modulate()?;
@@ -58,6 +78,7 @@ fn run(grid_path: &str) -> Result<(), failure::Error> {
                 Event::MouseButtonUp {mouse_btn, x, y, ..} => gd.controls.action_mouse(mouse_btn, x, y, KeyStatus::Released),
                 Event::MouseButtonDown {mouse_btn, x, y, ..} => gd.controls.action_mouse(mouse_btn, x, y,KeyStatus::Pressed),
                 Event::MouseMotion {x, y, ..} => gd.controls.action_mouse_move(x, y),
+                Event::MouseWheel {y, ..} => gd.controls.action_mouse_wheel(y),
                 _ => {},
             }
         }
@@ -65,8 +86,13 @@ fn run(grid_path: &str) -> Result<(), failure::Error> {
             break
         }
         gd.process_input()?;
-        gd.render();
-        window.gl_swap_window();
+        gd.render()?;
+        match headless_frames {
+            Some(frames) if gd.get_recorded_frames() >= frames => break,
+            Some(_) => {},
+            None => window.gl_swap_window(),
+        }
     }
+    gd.stop_recording();
     Ok(())
 }